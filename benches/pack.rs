@@ -0,0 +1,77 @@
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use scp_pack::pack_archiver::PackArchiver;
+use sha1::{Digest, Sha1};
+
+const FILE_COUNT: usize = 10_000;
+const FILE_SIZE: usize = 4096;
+
+/// repositoryに`FILE_COUNT`個のファイルを持つpackディレクトリを作成
+///
+/// 半分はJSON風のテキスト（圧縮される）、半分はgzipのヘッダを付けた乱数（無圧縮で保存される）
+fn synthetic_pack(dir: &Path) -> u64 {
+    let repository = dir.join("repository");
+    fs::create_dir_all(&repository).unwrap();
+    fs::write(dir.join("db.json"), "{}").unwrap();
+
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    let mut total = 0;
+    for i in 0..FILE_COUNT {
+        let content = if i % 2 == 0 {
+            let mut text = String::from("{\"entities\":[");
+            while text.len() < FILE_SIZE {
+                text.push_str(&format!("{{\"archetype\":{},\"data\":[{}]}},", next() % 16, next() % 100_000));
+            }
+            text.into_bytes()
+        } else {
+            let mut bytes = vec![0x1F, 0x8B];
+            while bytes.len() < FILE_SIZE {
+                bytes.extend_from_slice(&next().to_le_bytes());
+            }
+            bytes
+        };
+
+        total += content.len() as u64;
+        let name = format!("{:x}", Sha1::digest(&content));
+        fs::write(repository.join(name), content).unwrap();
+    }
+    total
+}
+
+fn pack(c: &mut Criterion) {
+    let dir: PathBuf = std::env::temp_dir().join(format!("scp-pack-bench-{}", std::process::id()));
+    let total = synthetic_pack(&dir);
+    let tree = PackArchiver::new().build(&dir).unwrap();
+
+    let mut group = c.benchmark_group("pack 10k files");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(total));
+
+    let max_jobs = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let mut jobs_list = vec![1, 2, 4, max_jobs];
+    jobs_list.retain(|jobs| *jobs <= max_jobs);
+    jobs_list.dedup();
+
+    for jobs in jobs_list {
+        let archiver = PackArchiver::new().with_jobs(Some(jobs));
+        group.bench_with_input(BenchmarkId::new("jobs", jobs), &archiver, |b, archiver| {
+            b.iter(|| archiver.write_archive(&tree, Cursor::new(Vec::new())).unwrap());
+        });
+    }
+
+    group.finish();
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+criterion_group!(benches, pack);
+criterion_main!(benches);
@@ -0,0 +1,354 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use serde::Serialize;
+use zip::CompressionMethod;
+
+use crate::utils::glob_match;
+
+/// エントリの圧縮方法（Stored以外はそれぞれ同名のcargo featureで有効にする）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Compression {
+    /// 無圧縮で保存
+    Stored,
+    #[cfg(feature = "deflate")]
+    Deflate,
+    #[cfg(feature = "bzip2")]
+    Bzip2,
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl Compression {
+    /// 名前とcargo featureの一覧（このビルドで無効なものも含む）
+    pub const NAMES: [&'static str; 4] = ["stored", "deflate", "bzip2", "zstd"];
+
+    pub fn method(self) -> CompressionMethod {
+        match self {
+            Compression::Stored => CompressionMethod::Stored,
+            #[cfg(feature = "deflate")]
+            Compression::Deflate => CompressionMethod::Deflated,
+            #[cfg(feature = "bzip2")]
+            Compression::Bzip2 => CompressionMethod::Bzip2,
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => CompressionMethod::Zstd,
+        }
+    }
+
+    /// ZIPの圧縮方法に対応するもの（このビルドで無効なものはNone）
+    pub fn from_method(method: CompressionMethod) -> Option<Self> {
+        match method {
+            CompressionMethod::Stored => Some(Compression::Stored),
+            #[cfg(feature = "deflate")]
+            CompressionMethod::Deflated => Some(Compression::Deflate),
+            #[cfg(feature = "bzip2")]
+            CompressionMethod::Bzip2 => Some(Compression::Bzip2),
+            #[cfg(feature = "zstd")]
+            CompressionMethod::Zstd => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    /// 指定できる圧縮レベルの範囲（Storedはレベルを持たない）
+    pub fn level_range(self) -> Option<RangeInclusive<i64>> {
+        match self {
+            Compression::Stored => None,
+            #[cfg(feature = "deflate")]
+            Compression::Deflate => Some(0..=9),
+            #[cfg(feature = "bzip2")]
+            Compression::Bzip2 => Some(1..=9),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Some(1..=22),
+        }
+    }
+
+    /// レベルを指定しない場合のレベル
+    pub fn default_level(self) -> Option<i64> {
+        match self {
+            Compression::Stored => None,
+            #[cfg(feature = "deflate")]
+            Compression::Deflate => Some(6),
+            #[cfg(feature = "bzip2")]
+            Compression::Bzip2 => Some(6),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Some(3),
+        }
+    }
+
+    /// 実際にZIPに書き込む圧縮方法とレベル（Deflateのレベル0は無圧縮）
+    pub fn with_level(self, level: Option<i64>) -> (Self, Option<i64>) {
+        match (self, level) {
+            #[cfg(feature = "deflate")]
+            (Compression::Deflate, Some(0)) => (Compression::Stored, None),
+            (compression, _) if compression.level_range().is_none() => (compression, None),
+            (compression, level) => (compression, level.or(compression.default_level())),
+        }
+    }
+
+    /// 圧縮レベルがこの方法で使えるか確認
+    pub fn check_level(self, level: i64) -> Result<(), String> {
+        match self.level_range() {
+            Some(range) if range.contains(&level) => Ok(()),
+            Some(range) => Err(format!(
+                "Compression level {} is out of range for {} ({}-{})",
+                level,
+                self,
+                range.start(),
+                range.end()
+            )),
+            None => Err(format!("{} does not take a compression level", self)),
+        }
+    }
+}
+
+impl Default for Compression {
+    /// Deflateが無効なビルドでは無圧縮
+    fn default() -> Self {
+        #[cfg(feature = "deflate")]
+        return Compression::Deflate;
+        #[cfg(not(feature = "deflate"))]
+        return Compression::Stored;
+    }
+}
+
+impl FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stored" => Ok(Compression::Stored),
+            #[cfg(feature = "deflate")]
+            "deflate" => Ok(Compression::Deflate),
+            #[cfg(feature = "bzip2")]
+            "bzip2" => Ok(Compression::Bzip2),
+            #[cfg(feature = "zstd")]
+            "zstd" => Ok(Compression::Zstd),
+            _ if Self::NAMES.contains(&s) => Err(format!("{} is not enabled in this build (enable the `{}` feature)", s, s)),
+            _ => Err(format!("Unknown compression: {} (expected {})", s, Self::NAMES.join(", "))),
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Compression::Stored => write!(f, "stored"),
+            #[cfg(feature = "deflate")]
+            Compression::Deflate => write!(f, "deflate"),
+            #[cfg(feature = "bzip2")]
+            Compression::Bzip2 => write!(f, "bzip2"),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => write!(f, "zstd"),
+        }
+    }
+}
+
+/// このビルドで展開できる圧縮方法か（無効にしたfeatureの方法はzipクレートが`Unsupported`として読む）
+#[allow(deprecated)]
+pub fn is_readable(method: CompressionMethod) -> bool {
+    !matches!(method, CompressionMethod::Unsupported(_))
+}
+
+/// ZIPの圧縮方法の名前（このビルドで読めないものも含む）
+pub fn method_name(method: CompressionMethod) -> String {
+    if let Some(compression) = Compression::from_method(method) {
+        return compression.to_string();
+    }
+
+    match method {
+        CompressionMethod::DEFLATE => "deflate".to_string(),
+        CompressionMethod::BZIP2 => "bzip2".to_string(),
+        CompressionMethod::ZSTD => "zstd".to_string(),
+        CompressionMethod::DEFLATE64 => "deflate64".to_string(),
+        CompressionMethod::LZMA => "lzma".to_string(),
+        CompressionMethod::XZ => "xz".to_string(),
+        method => format!("{:?}", method).to_lowercase(),
+    }
+}
+
+/// 先頭のバイト列から判別できる、すでに圧縮されている形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ContentKind {
+    Png,
+    Jpeg,
+    Ogg,
+    Mp3,
+    Gzip,
+}
+
+impl ContentKind {
+    pub const ALL: [ContentKind; 5] = [
+        ContentKind::Png,
+        ContentKind::Jpeg,
+        ContentKind::Ogg,
+        ContentKind::Mp3,
+        ContentKind::Gzip,
+    ];
+
+    /// マジックバイトから形式を判別
+    pub fn sniff(data: &[u8]) -> Option<Self> {
+        match data {
+            [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, ..] => Some(ContentKind::Png),
+            [0xFF, 0xD8, 0xFF, ..] => Some(ContentKind::Jpeg),
+            [b'O', b'g', b'g', b'S', ..] => Some(ContentKind::Ogg),
+            // ID3タグ、またはMPEGオーディオのフレーム同期（11ビットの1）
+            [b'I', b'D', b'3', ..] => Some(ContentKind::Mp3),
+            [0xFF, second, ..] if second & 0xE0 == 0xE0 => Some(ContentKind::Mp3),
+            [0x1F, 0x8B, ..] => Some(ContentKind::Gzip),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ContentKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ContentKind::Png => write!(f, "png"),
+            ContentKind::Jpeg => write!(f, "jpeg"),
+            ContentKind::Ogg => write!(f, "ogg"),
+            ContentKind::Mp3 => write!(f, "mp3"),
+            ContentKind::Gzip => write!(f, "gzip"),
+        }
+    }
+}
+
+/// パスのパターンごとの圧縮方法の指定（例: `static/sonolus/repository/*=deflate`）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompressionRule {
+    /// SCP内のパス（`*`・`?`を使える）
+    pub pattern: String,
+    pub compression: Compression,
+}
+
+impl FromStr for CompressionRule {
+    type Err = String;
+
+    /// `<pattern>=<compression>`形式
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (pattern, compression) = s.rsplit_once('=')
+            .filter(|(pattern, _)| !pattern.is_empty())
+            .ok_or_else(|| format!("Expected <pattern>=<compression>: {}", s))?;
+
+        Ok(Self {
+            pattern: pattern.to_string(),
+            compression: compression.parse()?,
+        })
+    }
+}
+
+impl fmt::Display for CompressionRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}={}", self.pattern, self.compression)
+    }
+}
+
+/// エントリの圧縮方法を決めた規則
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum RuleMatch {
+    /// パターンの指定
+    Override { pattern: String },
+    /// すでに圧縮されている形式
+    Content { content: ContentKind },
+    /// それ以外（JSONなど）
+    Default,
+}
+
+impl fmt::Display for RuleMatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuleMatch::Override { pattern } => write!(f, "{}", pattern),
+            RuleMatch::Content { content } => write!(f, "{} content", content),
+            RuleMatch::Default => write!(f, "default"),
+        }
+    }
+}
+
+/// エントリの圧縮方法を選ぶ（パターンの指定が優先、次にすでに圧縮されている形式は無圧縮、それ以外は`default`）
+pub fn select_compression(
+    overrides: &[CompressionRule],
+    default: Compression,
+    path: &str,
+    data: &[u8],
+) -> (RuleMatch, Compression) {
+    if let Some(rule) = overrides.iter().find(|rule| glob_match(&rule.pattern, path)) {
+        return (RuleMatch::Override { pattern: rule.pattern.clone() }, rule.compression);
+    }
+
+    match ContentKind::sniff(data) {
+        Some(content) => (RuleMatch::Content { content }, Compression::Stored),
+        None => (RuleMatch::Default, default),
+    }
+}
+
+/// 規則ごとの圧縮結果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleSavings {
+    #[serde(flatten)]
+    pub rule: RuleMatch,
+    pub compression: Compression,
+    pub entries: usize,
+    pub original_size: u64,
+    pub compressed_size: u64,
+}
+
+impl RuleSavings {
+    /// 圧縮で減ったバイト数
+    pub fn saved(&self) -> i64 {
+        self.original_size as i64 - self.compressed_size as i64
+    }
+}
+
+/// SCPファイルの圧縮結果（パターンの指定、形式、既定の順）
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CompressionReport {
+    pub rules: Vec<RuleSavings>,
+}
+
+impl CompressionReport {
+    pub fn record(&mut self, rule: RuleMatch, compression: Compression, original_size: u64, compressed_size: u64) {
+        let index = match self.rules.iter().position(|savings| savings.rule == rule) {
+            Some(index) => index,
+            None => {
+                self.rules.push(RuleSavings { rule, compression, entries: 0, original_size: 0, compressed_size: 0 });
+                self.rules.len() - 1
+            }
+        };
+
+        let savings = &mut self.rules[index];
+        savings.entries += 1;
+        savings.original_size += original_size;
+        savings.compressed_size += compressed_size;
+    }
+
+    /// 規則を出力順に並べる
+    pub fn sort(&mut self, overrides: &[CompressionRule]) {
+        self.rules.sort_by_key(|savings| match &savings.rule {
+            RuleMatch::Override { pattern } => (0, overrides.iter().position(|rule| &rule.pattern == pattern).unwrap_or(0)),
+            RuleMatch::Content { content } => (1, ContentKind::ALL.iter().position(|kind| kind == content).unwrap_or(0)),
+            RuleMatch::Default => (2, 0),
+        });
+    }
+}
+
+impl fmt::Display for CompressionReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Compression:")?;
+        for savings in &self.rules {
+            write!(
+                f,
+                "\n  {} ({}): {} entries, {} -> {} bytes (saved {} bytes)",
+                savings.rule,
+                savings.compression,
+                savings.entries,
+                savings.original_size,
+                savings.compressed_size,
+                savings.saved()
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::model::ItemType;
+
+/// db.jsonの`config`に記述する静的ファイル生成の設定
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackConfig {
+    /// 各カテゴリの`info`に出力するセクション
+    #[serde(default = "default_info_sections")]
+    pub info_sections: Vec<SectionConfig>,
+    /// カテゴリごとに`info_sections`を上書きする
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub category_info_sections: BTreeMap<ItemType, Vec<SectionConfig>>,
+    /// 各アイテムの個別ページに出力するおすすめセクション
+    #[serde(default = "default_recommend_sections")]
+    pub recommend_sections: Vec<RecommendConfig>,
+    /// カテゴリごとに`recommend_sections`を上書きする
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub category_recommend_sections: BTreeMap<ItemType, Vec<RecommendConfig>>,
+    /// 手動で選んだおすすめアイテム（カテゴリ → アイテム名 → おすすめするアイテム名）
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub recommendations: BTreeMap<ItemType, BTreeMap<String, Vec<String>>>,
+    /// 各カテゴリの`info`に出力する検索オプション
+    #[serde(default)]
+    pub search: SearchConfig,
+    /// listファイル1ページあたりのアイテム数（`--page-size`が優先）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<usize>,
+}
+
+impl PackConfig {
+    /// カテゴリの`info`に出力するセクション
+    pub fn info_sections(&self, item_type: ItemType) -> &[SectionConfig] {
+        self.category_info_sections.get(&item_type)
+            .unwrap_or(&self.info_sections)
+    }
+
+    /// アイテムの個別ページに出力するおすすめセクション
+    pub fn recommend_sections(&self, item_type: ItemType) -> &[RecommendConfig] {
+        self.category_recommend_sections.get(&item_type)
+            .unwrap_or(&self.recommend_sections)
+    }
+    
+    /// 手動で選んだおすすめアイテム名
+    pub fn recommendations(&self, item_type: ItemType, name: &str) -> &[String] {
+        self.recommendations.get(&item_type)
+            .and_then(|items| items.get(name))
+            .map(|names| names.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Default for PackConfig {
+    fn default() -> Self {
+        Self {
+            info_sections: default_info_sections(),
+            category_info_sections: BTreeMap::new(),
+            recommend_sections: default_recommend_sections(),
+            category_recommend_sections: BTreeMap::new(),
+            recommendations: BTreeMap::new(),
+            search: SearchConfig::default(),
+            page_size: None,
+        }
+    }
+}
+
+fn default_info_sections() -> Vec<SectionConfig> {
+    vec![SectionConfig {
+        title: "#NEWEST".to_string(),
+        icon: None,
+        // 全件はlistにあるため、infoに一覧全体を重ねて出力しない
+        item_count: Some(5),
+        order: SectionOrder::Pack,
+    }]
+}
+
+fn default_recommend_sections() -> Vec<RecommendConfig> {
+    vec![RecommendConfig {
+        title: "#RECOMMENDED".to_string(),
+        icon: Some("star".to_string()),
+        strategy: RecommendStrategy::SameCategory,
+        // 全アイテムを並べると個別ページごとにカテゴリ全体が埋め込まれるため上限を設ける
+        item_count: Some(5),
+    }]
+}
+
+/// 検索オプションの設定（省略時は検索なし）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SearchConfig {
+    /// キーワード検索（テキスト入力）
+    pub keywords: bool,
+    /// タグによる絞り込み（複数選択）
+    pub tags: bool,
+    /// 作者による絞り込み（単一選択）
+    pub authors: bool,
+}
+
+/// アイテム一覧セクションの設定
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SectionConfig {
+    /// セクションのタイトル（"#NEWEST"などのテキストコードも可）
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    /// 表示するアイテム数の上限（省略時は全アイテム）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item_count: Option<usize>,
+    #[serde(default)]
+    pub order: SectionOrder,
+}
+
+/// セクション内のアイテムの並び順
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SectionOrder {
+    /// db.jsonの記述順
+    #[default]
+    Pack,
+    /// db.jsonの逆順
+    Reverse,
+    /// アイテム名順
+    Name,
+    /// バージョンの降順
+    Version,
+}
+
+impl SectionOrder {
+    /// 静的ファイル形式のアイテムを並べ替える
+    pub fn sort(&self, items: &mut [Value]) {
+        match self {
+            SectionOrder::Pack => {}
+            SectionOrder::Reverse => items.reverse(),
+            SectionOrder::Name => items.sort_by(|a, b| {
+                a["name"].as_str().cmp(&b["name"].as_str())
+            }),
+            SectionOrder::Version => items.sort_by(|a, b| {
+                b["version"].as_u64().cmp(&a["version"].as_u64())
+            }),
+        }
+    }
+}
+
+/// 個別ページのおすすめセクションの設定
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecommendConfig {
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub strategy: RecommendStrategy,
+    /// 表示するアイテム数の上限（省略時は全アイテム）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item_count: Option<usize>,
+}
+
+impl RecommendConfig {
+    /// 静的ファイル形式のアイテム一覧からおすすめを選ぶ（対象アイテム自身は除く）
+    pub fn select(&self, item: &Value, candidates: &[Value], recommendations: &[String]) -> Vec<Value> {
+        // 対象アイテムの次から順に並べ、末尾まで来たら先頭に戻る
+        let position = candidates.iter()
+            .position(|other| other["name"] == item["name"])
+            .map(|index| index + 1)
+            .unwrap_or(0);
+        let others = candidates[position..].iter()
+            .chain(&candidates[..position])
+            .filter(|other| other["name"] != item["name"]);
+        
+        // 上限までのアイテムだけを複製する
+        let count = self.item_count.unwrap_or(usize::MAX);
+        match self.strategy {
+            RecommendStrategy::SameCategory => others.take(count).cloned().collect(),
+            RecommendStrategy::SameAuthor => others
+                .filter(|other| other["author"] == item["author"])
+                .take(count)
+                .cloned()
+                .collect(),
+            RecommendStrategy::SharedTags => {
+                // 共通するタグが多い順（同数なら対象アイテムの次からの順）
+                let tags = tag_titles(item);
+                let mut scored: Vec<(usize, &Value)> = others
+                    .map(|other| {
+                        let shared = tag_titles(other).iter().filter(|tag| tags.contains(tag)).count();
+                        (shared, other)
+                    })
+                    .filter(|(shared, _)| *shared > 0)
+                    .collect();
+                scored.sort_by_key(|(shared, _)| std::cmp::Reverse(*shared));
+                scored.into_iter().take(count).map(|(_, other)| other.clone()).collect()
+            }
+            RecommendStrategy::Curated => recommendations.iter()
+                .filter_map(|name| candidates.iter().find(|other| other["name"] == name.as_str()))
+                .take(count)
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+/// タグのタイトル一覧
+fn tag_titles(item: &Value) -> Vec<&Value> {
+    item["tags"].as_array()
+        .map(|tags| tags.iter().map(|tag| &tag["title"]).collect())
+        .unwrap_or_default()
+}
+
+/// おすすめアイテムの選び方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RecommendStrategy {
+    /// 同じカテゴリの他のアイテム
+    #[default]
+    SameCategory,
+    /// 作者が同じアイテム
+    SameAuthor,
+    /// タグを共有するアイテム
+    SharedTags,
+    /// `recommendations`で手動指定したアイテム
+    Curated,
+}
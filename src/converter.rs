@@ -1,8 +1,20 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+use crate::compression::{Compression, CompressionRule};
+use crate::diff::PackDiff;
 use crate::error::Result;
+use crate::gc::{collect_garbage, GcReport};
+use crate::graph::DependencyGraph;
+use crate::integrity::{IntegrityReport, PackIntegrity};
+use crate::locale::LocaleFallback;
+use crate::merge::{ConflictPolicy, MergeReport, PackMerger};
+use crate::model::{ItemType, PackDatabase};
 use crate::pack_archiver::PackArchiver;
+use crate::patch::{apply_patch, make_patch, PatchReport};
 use crate::pack_extractor::PackExtractor;
+use crate::select::ItemPattern;
+use crate::utils::{locale_output_path, validate_pack_dir};
+use crate::verify::{verify_roundtrip, RoundtripReport};
 
 pub struct Converter {
     archiver: PackArchiver,
@@ -17,11 +29,83 @@ impl Converter {
         }
     }
     
+    /// 現在の圧縮方法のレベルを設定
     pub fn with_compression_level(mut self, level: i64) -> Self {
         self.archiver = self.archiver.with_compression_level(level);
         self
     }
     
+    /// JSONなどの圧縮方法と、そのレベルを設定（Noneなら既定値）
+    pub fn with_compression(mut self, compression: Compression, level: Option<i64>) -> Self {
+        self.archiver = self.archiver.with_compression(compression);
+        if let Some(level) = level {
+            self.archiver = self.archiver.with_compression_level(level);
+        }
+        self
+    }
+    
+    /// 圧縮に使うスレッド数を設定（NoneならCPUの数）
+    pub fn with_jobs(mut self, jobs: Option<usize>) -> Self {
+        self.archiver = self.archiver.with_jobs(jobs);
+        self
+    }
+    
+    /// パスのパターンごとの圧縮方法を設定
+    pub fn with_compression_overrides(mut self, overrides: Vec<CompressionRule>) -> Self {
+        self.archiver = self.archiver.with_compression_overrides(overrides);
+        self
+    }
+    
+    /// 静的ファイルに出力する言語の優先順位を設定
+    pub fn with_locale(mut self, locale: LocaleFallback) -> Self {
+        self.archiver = self.archiver.with_locale(locale);
+        self
+    }
+    
+    /// listファイル1ページあたりのアイテム数を設定
+    pub fn with_page_size(mut self, page_size: Option<usize>) -> Self {
+        self.archiver = self.archiver.with_page_size(page_size);
+        self
+    }
+    
+    /// packageファイルの`shouldUpdate`を設定
+    pub fn with_should_update(mut self, should_update: Option<bool>) -> Self {
+        self.archiver = self.archiver.with_should_update(should_update);
+        self
+    }
+    
+    /// 参照されているrepositoryのファイルだけをSCPに含めるかを設定
+    pub fn with_reachable_only(mut self, reachable_only: bool) -> Self {
+        self.archiver = self.archiver.with_reachable_only(reachable_only);
+        self
+    }
+    
+    /// SCPに出力するアイテムを指定（参照しているアイテムも含める）
+    pub fn with_includes(mut self, includes: Vec<ItemPattern>) -> Self {
+        self.archiver = self.archiver.with_includes(includes);
+        self
+    }
+    
+    /// 同じpackから常に同じバイト列のSCPを作るかを設定
+    pub fn with_deterministic(mut self, deterministic: bool, source_date_epoch: Option<i64>) -> Self {
+        self.archiver = self.archiver
+            .with_deterministic(deterministic)
+            .with_source_date_epoch(source_date_epoch);
+        self
+    }
+    
+    /// SCPファイルのテキストをどの言語として取り込むかを設定
+    pub fn with_extract_locale(mut self, locale: &str) -> Self {
+        self.extractor = self.extractor.with_locale(locale);
+        self
+    }
+    
+    /// SCPファイルを展開する際に再生成できない内容も保存するかを設定
+    pub fn with_lossless(mut self, lossless: bool) -> Self {
+        self.extractor = self.extractor.with_lossless(lossless);
+        self
+    }
+    
     /// packディレクトリをSCPファイルに変換
     pub fn pack_to_scp(&self, pack_dir: &Path, scp_file: &Path) -> Result<()> {
         println!("Converting pack directory to SCP file...");
@@ -32,6 +116,22 @@ impl Converter {
         Ok(())
     }
     
+    /// packディレクトリをロケールごとのSCPファイルに変換（例: output.ja.scp, output.en.scp）
+    pub fn pack_to_scp_per_locale(&self, pack_dir: &Path, scp_file: &Path, locales: &[String]) -> Result<()> {
+        for locale in locales {
+            let output = locale_output_path(scp_file, locale);
+            let archiver = self.archiver.clone()
+                .with_locale(self.archiver.locale().with_primary(locale));
+            
+            println!("Converting pack directory to SCP file ({})...", locale);
+            println!("Input: {}", pack_dir.display());
+            println!("Output: {}", output.display());
+            
+            archiver.archive(pack_dir, &output)?;
+        }
+        Ok(())
+    }
+    
     /// SCPファイルをpackディレクトリに変換
     pub fn scp_to_pack(&self, scp_file: &Path, pack_dir: &Path) -> Result<()> {
         println!("Converting SCP file to pack directory...");
@@ -42,10 +142,88 @@ impl Converter {
         Ok(())
     }
     
+    /// ロケール別のSCPファイルを1つのpackディレクトリに結合して変換
+    pub fn merge_locale_scps_to_pack(&self, scp_files: &[(PathBuf, String)], pack_dir: &Path) -> Result<()> {
+        println!("Merging locale SCP files to pack directory...");
+        println!("Output: {}", pack_dir.display());
+        
+        self.extractor.extract_merged(scp_files, pack_dir)?;
+        Ok(())
+    }
+    
+    /// packディレクトリまたはSCPファイルを往復変換し、元の内容と比較
+    pub fn verify_roundtrip(&self, input: &Path) -> Result<RoundtripReport> {
+        verify_roundtrip(&self.archiver, &self.extractor, input)
+    }
+    
+    /// repositoryのファイルのハッシュとSRLの参照を検証
+    pub fn check_integrity(&self, input: &Path) -> Result<IntegrityReport> {
+        let integrity = PackIntegrity::load(input)?;
+        Ok(integrity.check(&input.display().to_string()))
+    }
+    
+    /// packディレクトリの参照されていないrepositoryのファイルを一覧（deleteがtrueなら削除）
+    pub fn gc_repository(&self, pack_dir: &Path, delete: bool) -> Result<GcReport> {
+        collect_garbage(pack_dir, delete)
+    }
+    
+    /// packディレクトリのアイテムの依存関係グラフを作成し、参照を検証
+    pub fn dependency_graph(&self, pack_dir: &Path) -> Result<DependencyGraph> {
+        validate_pack_dir(pack_dir)?;
+        DependencyGraph::build(&PackDatabase::load(pack_dir)?)
+    }
+    
+    /// 複数のpackディレクトリ・SCPファイルを1つに結合（解決できない衝突があれば書き込まない）
+    pub fn merge_packs(
+        &self,
+        inputs: &[PathBuf],
+        output: &Path,
+        policy: ConflictPolicy,
+        rename_prefix: Option<String>,
+    ) -> Result<MergeReport> {
+        PackMerger::new()
+            .with_policy(policy)
+            .with_rename_prefix(rename_prefix)
+            .merge_inputs(&self.extractor, &self.archiver, inputs, output)
+    }
+    
+    /// 2つのpackディレクトリ・SCPファイルをアイテム単位で比較
+    pub fn diff(&self, old: &Path, new: &Path) -> Result<PackDiff> {
+        PackDiff::compare(&self.extractor, old, new)
+    }
+    
+    /// 元のSCPファイルと新しいSCPファイルの差分パッチを作成
+    pub fn make_patch(&self, base: &Path, target: &Path, patch_file: &Path) -> Result<PatchReport> {
+        make_patch(base, target, patch_file)
+    }
+    
+    /// 元のSCPファイルにパッチを適用して新しいSCPファイルを作成
+    pub fn apply_patch(&self, base: &Path, patch_file: &Path, scp_file: &Path) -> Result<()> {
+        println!("Applying patch {} to {}...", patch_file.display(), base.display());
+        
+        let manifest = apply_patch(base, patch_file, scp_file)?;
+        println!("Successfully created SCP file: {} (SHA-1 {})", scp_file.display(), manifest.target.sha1);
+        Ok(())
+    }
+    
     /// SCPファイルの内容を表示
     pub fn list_scp_contents(&self, scp_file: &Path) -> Result<()> {
+        self.print_contents(scp_file, None)
+    }
+    
+    /// SCPファイル内のカテゴリのファイルを一覧表示
+    pub fn list_category_contents(&self, scp_file: &Path, item_type: ItemType) -> Result<()> {
+        self.print_contents(scp_file, Some(item_type))
+    }
+    
+    fn print_contents(&self, scp_file: &Path, item_type: Option<ItemType>) -> Result<()> {
         println!("Contents of {}:", scp_file.display());
-        let contents = self.extractor.list_contents(scp_file)?;
+        let mut contents = self.extractor.list_contents(scp_file)?;
+        
+        if let Some(item_type) = item_type {
+            let prefix = format!("static/sonolus/{}/", item_type.path());
+            contents.retain(|content| content.starts_with(&prefix));
+        }
         
         for (index, content) in contents.iter().enumerate() {
             println!("  {}: {}", index + 1, content);
@@ -0,0 +1,216 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::path::Path;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::Result;
+use crate::json_diff::{diff_json, FieldChange};
+use crate::model::{ItemType, PackDatabase};
+use crate::pack_extractor::PackExtractor;
+use crate::source::PackSource;
+
+/// アイテム単位の変更
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ItemChange {
+    Added,
+    Removed,
+    Modified { fields: Vec<FieldChange> },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemDiff {
+    pub item_type: ItemType,
+    pub name: String,
+    #[serde(flatten)]
+    pub change: ItemChange,
+}
+
+/// repositoryのファイルの変更（ファイル名はハッシュなので追加・削除だけ）
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum BlobChange {
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BlobDiff {
+    pub name: String,
+    pub size: u64,
+    #[serde(flatten)]
+    pub change: BlobChange,
+}
+
+/// 2つのpackの差分
+#[derive(Debug, Clone, Serialize)]
+pub struct PackDiff {
+    pub old: String,
+    pub new: String,
+    pub items: Vec<ItemDiff>,
+    pub repository: Vec<BlobDiff>,
+    /// `info`・`config`などアイテム以外の変更
+    pub settings: Vec<FieldChange>,
+}
+
+impl PackDiff {
+    /// packディレクトリまたはSCPファイルを比較（SCPファイルは`unpack`と同じように展開してから比較）
+    pub fn compare(extractor: &PackExtractor, old: &Path, new: &Path) -> Result<Self> {
+        let old = PackSource::load(extractor, old)?;
+        let new = PackSource::load(extractor, new)?;
+        Self::between(&old, &new)
+    }
+
+    pub fn between(old: &PackSource, new: &PackSource) -> Result<Self> {
+        Ok(Self {
+            old: old.label.clone(),
+            new: new.label.clone(),
+            items: diff_items(&old.db, &new.db)?,
+            repository: diff_repository(old, new)?,
+            settings: diff_json(&settings_value(&old.db)?, &settings_value(&new.db)?),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty() && self.repository.is_empty() && self.settings.is_empty()
+    }
+
+    fn count_items(&self, f: impl Fn(&ItemChange) -> bool) -> usize {
+        self.items.iter().filter(|diff| f(&diff.change)).count()
+    }
+
+    fn blob_total(&self, f: impl Fn(&BlobChange) -> bool) -> (usize, u64) {
+        self.repository.iter()
+            .filter(|diff| f(&diff.change))
+            .fold((0, 0), |(count, size), diff| (count + 1, size + diff.size))
+    }
+}
+
+impl fmt::Display for PackDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Diff: {} -> {}", self.old, self.new)?;
+
+        for item_type in ItemType::ALL {
+            let diffs: Vec<&ItemDiff> = self.items.iter().filter(|diff| diff.item_type == item_type).collect();
+            if diffs.is_empty() {
+                continue;
+            }
+
+            writeln!(f, "{}:", item_type.path())?;
+            for diff in diffs {
+                match &diff.change {
+                    ItemChange::Added => writeln!(f, "  + {}", diff.name)?,
+                    ItemChange::Removed => writeln!(f, "  - {}", diff.name)?,
+                    ItemChange::Modified { fields } => {
+                        writeln!(f, "  ~ {}", diff.name)?;
+                        for field in fields {
+                            writeln!(f, "      {}", field)?;
+                        }
+                    }
+                }
+            }
+        }
+
+        if !self.repository.is_empty() {
+            writeln!(f, "repository:")?;
+            for diff in &self.repository {
+                let sign = match diff.change {
+                    BlobChange::Added => '+',
+                    BlobChange::Removed => '-',
+                };
+                writeln!(f, "  {} {} ({} bytes)", sign, diff.name, diff.size)?;
+            }
+        }
+
+        if !self.settings.is_empty() {
+            writeln!(f, "settings:")?;
+            for field in &self.settings {
+                writeln!(f, "  {}", field)?;
+            }
+        }
+
+        if self.is_empty() {
+            return write!(f, "No differences");
+        }
+
+        let (added_blobs, added_size) = self.blob_total(|change| matches!(change, BlobChange::Added));
+        let (removed_blobs, removed_size) = self.blob_total(|change| matches!(change, BlobChange::Removed));
+        write!(
+            f,
+            "Items: {} added, {} removed, {} modified; repository: {} added ({} bytes), {} removed ({} bytes); settings: {} changes",
+            self.count_items(|change| matches!(change, ItemChange::Added)),
+            self.count_items(|change| matches!(change, ItemChange::Removed)),
+            self.count_items(|change| matches!(change, ItemChange::Modified { .. })),
+            added_blobs,
+            added_size,
+            removed_blobs,
+            removed_size,
+            self.settings.len()
+        )
+    }
+}
+
+/// カテゴリごとに、新しい側の順で追加・変更、続けて古い側の順で削除を並べる
+fn diff_items(old: &PackDatabase, new: &PackDatabase) -> Result<Vec<ItemDiff>> {
+    let mut diffs = Vec::new();
+
+    for item_type in ItemType::ALL {
+        let old_items = old.items(item_type).into_iter()
+            .map(|item| Ok((item.name().to_string(), item.to_value()?)))
+            .collect::<Result<BTreeMap<String, Value>>>()?;
+        let new_items = new.items(item_type);
+
+        for item in &new_items {
+            let change = match old_items.get(item.name()) {
+                None => Some(ItemChange::Added),
+                Some(old_value) => {
+                    let fields = diff_json(old_value, &item.to_value()?);
+                    (!fields.is_empty()).then_some(ItemChange::Modified { fields })
+                }
+            };
+            if let Some(change) = change {
+                diffs.push(ItemDiff { item_type, name: item.name().to_string(), change });
+            }
+        }
+
+        for item in old.items(item_type) {
+            if !new_items.iter().any(|new_item| new_item.name() == item.name()) {
+                diffs.push(ItemDiff { item_type, name: item.name().to_string(), change: ItemChange::Removed });
+            }
+        }
+    }
+
+    Ok(diffs)
+}
+
+fn diff_repository(old: &PackSource, new: &PackSource) -> Result<Vec<BlobDiff>> {
+    let old_names: HashSet<&str> = old.repository.iter().map(|(name, _)| name.as_str()).collect();
+    let new_names: HashSet<&str> = new.repository.iter().map(|(name, _)| name.as_str()).collect();
+    let mut diffs = Vec::new();
+
+    for (name, data) in &new.repository {
+        if !old_names.contains(name.as_str()) {
+            diffs.push(BlobDiff { name: name.clone(), size: data.size()?, change: BlobChange::Added });
+        }
+    }
+    for (name, data) in &old.repository {
+        if !new_names.contains(name.as_str()) {
+            diffs.push(BlobDiff { name: name.clone(), size: data.size()?, change: BlobChange::Removed });
+        }
+    }
+
+    Ok(diffs)
+}
+
+/// db.jsonからアイテムのカテゴリを除いたもの
+fn settings_value(db: &PackDatabase) -> Result<Value> {
+    let mut value = serde_json::to_value(db)?;
+    if let Value::Object(object) = &mut value {
+        for item_type in ItemType::ALL {
+            object.remove(item_type.db_key());
+        }
+    }
+    Ok(value)
+}
@@ -7,6 +7,11 @@ pub enum ScpError {
     Json(serde_json::Error),
     InvalidPath(String),
     InvalidFormat(String),
+    InvalidItem {
+        category: String,
+        name: String,
+        source: serde_json::Error,
+    },
 }
 
 impl fmt::Display for ScpError {
@@ -17,6 +22,9 @@ impl fmt::Display for ScpError {
             ScpError::Json(err) => write!(f, "JSON error: {}", err),
             ScpError::InvalidPath(path) => write!(f, "Invalid path: {}", path),
             ScpError::InvalidFormat(msg) => write!(f, "Invalid format: {}", msg),
+            ScpError::InvalidItem { category, name, source } => {
+                write!(f, "Invalid item {}/{}: {}", category, name, source)
+            }
         }
     }
 }
@@ -0,0 +1,97 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use walkdir::WalkDir;
+
+use crate::error::Result;
+use crate::integrity::pack_references;
+use crate::utils::validate_pack_dir;
+
+/// どのSRLからも参照されていないrepositoryのファイル
+#[derive(Debug, Clone, Serialize)]
+pub struct OrphanFile {
+    pub name: String,
+    pub size: u64,
+    #[serde(skip)]
+    pub path: PathBuf,
+}
+
+/// `gc`の結果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GcReport {
+    pub pack_dir: String,
+    pub orphans: Vec<OrphanFile>,
+    pub total_size: u64,
+    /// 削除したか（falseなら一覧のみ）
+    pub deleted: bool,
+}
+
+impl fmt::Display for GcReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let action = if self.deleted { "Deleted" } else { "Unreferenced" };
+
+        writeln!(f, "Repository garbage collection: {}", self.pack_dir)?;
+        for orphan in &self.orphans {
+            writeln!(f, "  {}: {} ({} bytes)", action, orphan.name, orphan.size)?;
+        }
+        write!(f, "{} {} files ({} bytes)", action, self.orphans.len(), self.total_size)
+    }
+}
+
+/// db.jsonとextraディレクトリのSRLが参照しているハッシュ
+pub fn reachable_hashes(pack_dir: &Path) -> Result<HashSet<String>> {
+    Ok(pack_references(pack_dir)?
+        .into_iter()
+        .map(|reference| reference.hash)
+        .collect())
+}
+
+/// 参照されていないrepositoryのファイルを探す
+pub fn find_orphans(pack_dir: &Path) -> Result<Vec<OrphanFile>> {
+    validate_pack_dir(pack_dir)?;
+
+    let reachable = reachable_hashes(pack_dir)?;
+    let repo_dir = pack_dir.join("repository");
+    let mut orphans = Vec::new();
+
+    if repo_dir.exists() {
+        for entry in WalkDir::new(&repo_dir).sort_by_file_name() {
+            let entry = entry.map_err(|e| std::io::Error::other(
+                format!("Failed to read repository entry: {}", e)
+            ))?;
+
+            if entry.path().is_file() {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if !reachable.contains(&name) {
+                    orphans.push(OrphanFile {
+                        name,
+                        size: entry.path().metadata()?.len(),
+                        path: entry.path().to_path_buf(),
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(orphans)
+}
+
+/// 参照されていないrepositoryのファイルを一覧（deleteがtrueなら削除）
+pub fn collect_garbage(pack_dir: &Path, delete: bool) -> Result<GcReport> {
+    let orphans = find_orphans(pack_dir)?;
+
+    if delete {
+        for orphan in &orphans {
+            std::fs::remove_file(&orphan.path)?;
+        }
+    }
+
+    Ok(GcReport {
+        pack_dir: pack_dir.display().to_string(),
+        total_size: orphans.iter().map(|orphan| orphan.size).sum(),
+        orphans,
+        deleted: delete,
+    })
+}
@@ -0,0 +1,233 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use serde::Serialize;
+
+use crate::error::Result;
+use crate::json_diff::{diff_json, FieldChange};
+use crate::model::{Item, ItemType, PackDatabase};
+
+/// グラフのノードID（例: `levels/my-level`）
+fn node_id(item_type: ItemType, name: &str) -> String {
+    format!("{}/{}", item_type.path(), name)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphNode {
+    pub id: String,
+    pub item_type: ItemType,
+    pub name: String,
+    /// db.jsonのカテゴリには無く、他のアイテムに埋め込まれているだけのアイテム
+    pub embedded_only: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphEdge {
+    pub from: String,
+    pub to: String,
+    /// 参照しているフィールド（例: `engine`、`useSkin.item`、`levels[0]`）
+    pub field: String,
+    /// 名前ではなくアイテムを埋め込んでいるか
+    pub embedded: bool,
+}
+
+/// 参照の問題
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum GraphIssue {
+    /// 名前で参照しているアイテムがdb.jsonに無い
+    Dangling { from: String, to: String, field: String },
+    /// 埋め込まれたアイテムがdb.jsonの同名アイテムと異なる
+    Mismatched {
+        from: String,
+        to: String,
+        field: String,
+        differences: Vec<FieldChange>,
+    },
+}
+
+impl fmt::Display for GraphIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GraphIssue::Dangling { from, to, field } => {
+                write!(f, "dangling: {} {} refers to missing {}", from, field, to)
+            }
+            GraphIssue::Mismatched { from, to, field, differences } => {
+                write!(f, "mismatched: {} {} embeds a copy of {} that differs in {} fields", from, field, to, differences.len())?;
+                for difference in differences {
+                    write!(f, "\n      {}", difference)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// db.jsonのアイテムの依存関係グラフ
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+    pub issues: Vec<GraphIssue>,
+}
+
+impl DependencyGraph {
+    pub fn build(db: &PackDatabase) -> Result<Self> {
+        let mut top_level = BTreeMap::new();
+        let mut nodes = Vec::new();
+
+        for item_type in ItemType::ALL {
+            for item in db.items(item_type) {
+                top_level.insert(node_id(item_type, item.name()), item);
+                nodes.push(GraphNode {
+                    id: node_id(item_type, item.name()),
+                    item_type,
+                    name: item.name().to_string(),
+                    embedded_only: false,
+                });
+            }
+        }
+
+        let mut graph = Self {
+            nodes,
+            edges: Vec::new(),
+            issues: Vec::new(),
+        };
+
+        for item_type in ItemType::ALL {
+            for item in db.items(item_type) {
+                graph.add_links(&top_level, item_type, item)?;
+            }
+        }
+
+        Ok(graph)
+    }
+
+    /// アイテムの参照を辺として追加し、参照先を検証
+    fn add_links(
+        &mut self,
+        top_level: &BTreeMap<String, &dyn Item>,
+        item_type: ItemType,
+        item: &dyn Item,
+    ) -> Result<()> {
+        let from = node_id(item_type, item.name());
+
+        for link in item.links() {
+            let to = node_id(link.item_type, link.name);
+            self.edges.push(GraphEdge {
+                from: from.clone(),
+                to: to.clone(),
+                field: link.field.clone(),
+                embedded: link.embedded.is_some(),
+            });
+
+            match (link.embedded, top_level.get(&to)) {
+                (None, None) => self.issues.push(GraphIssue::Dangling {
+                    from: from.clone(),
+                    to,
+                    field: link.field,
+                }),
+                (Some(embedded), Some(original)) => {
+                    let differences = diff_json(&original.to_value()?, &embedded.to_value()?);
+                    if !differences.is_empty() {
+                        self.issues.push(GraphIssue::Mismatched {
+                            from: from.clone(),
+                            to,
+                            field: link.field,
+                            differences,
+                        });
+                    }
+                }
+                (Some(embedded), None) => {
+                    // 埋め込みにしか無いアイテムは、その参照もたどる
+                    if !self.nodes.iter().any(|node| node.id == to) {
+                        self.nodes.push(GraphNode {
+                            id: to,
+                            item_type: link.item_type,
+                            name: link.name.to_string(),
+                            embedded_only: true,
+                        });
+                    }
+                    self.add_links(top_level, link.item_type, embedded)?;
+                }
+                (None, Some(_)) => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Graphviz（DOT形式）に変換（参照先が無い辺は赤の破線）
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph pack {\n    rankdir=LR;\n    node [shape=box];\n");
+
+        for node in &self.nodes {
+            let style = if node.embedded_only { ", style=dashed" } else { "" };
+            dot.push_str(&format!(
+                "    {} [label={}{}];\n",
+                dot_string(&node.id),
+                dot_string(&format!("{}\n{}", node.item_type, node.name)),
+                style
+            ));
+        }
+
+        let missing: BTreeSet<&str> = self.issues.iter()
+            .filter_map(|issue| match issue {
+                GraphIssue::Dangling { to, .. } => Some(to.as_str()),
+                _ => None,
+            })
+            .collect();
+        for id in missing {
+            dot.push_str(&format!("    {} [color=red, style=dashed];\n", dot_string(id)));
+        }
+
+        for edge in &self.edges {
+            let mut attributes = vec![format!("label={}", dot_string(&edge.field))];
+            if edge.embedded {
+                attributes.push("style=bold".to_string());
+            }
+            let has_issue = self.issues.iter().any(|issue| match issue {
+                GraphIssue::Dangling { from, to, field } | GraphIssue::Mismatched { from, to, field, .. } => {
+                    *from == edge.from && *to == edge.to && *field == edge.field
+                }
+            });
+            if has_issue {
+                attributes.push("color=red".to_string());
+            }
+            dot.push_str(&format!(
+                "    {} -> {} [{}];\n",
+                dot_string(&edge.from),
+                dot_string(&edge.to),
+                attributes.join(", ")
+            ));
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl fmt::Display for DependencyGraph {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Dependency graph: {} items, {} references", self.nodes.len(), self.edges.len())?;
+
+        for issue in &self.issues {
+            writeln!(f, "  {}", issue)?;
+        }
+
+        if self.is_ok() {
+            write!(f, "OK: no issues")
+        } else {
+            write!(f, "FAILED: {} issues", self.issues.len())
+        }
+    }
+}
+
+/// DOTの文字列リテラル
+fn dot_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
@@ -0,0 +1,316 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::path::Path;
+use serde::Serialize;
+use serde_json::Value;
+use sha1::{Digest, Sha1};
+use walkdir::WalkDir;
+use zip::ZipArchive;
+
+use crate::error::Result;
+use crate::model::ItemType;
+use crate::utils::{normalize_path, repository_hash, validate_pack_dir, validate_scp_file, EXTRA_DIR};
+
+/// SRLのURLとして正しい値
+pub fn repository_url(hash: &str) -> String {
+    format!("/sonolus/repository/{}", hash)
+}
+
+/// データのSHA-1（16進小文字）
+pub fn sha1_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha1::digest(data))
+}
+
+/// 読み込みながらSHA-1を計算
+pub fn sha1_reader(reader: &mut impl Read) -> Result<String> {
+    let mut hasher = Sha1::new();
+    std::io::copy(reader, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// JSON内で見つかったSRL（`{"hash": ..., "url": ...}`）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SrlReference {
+    /// 参照元（packなら`skins/pixel`などのアイテム、SCPならエントリのパス）
+    pub location: String,
+    /// 参照元のJSON内のパス（例: `$.thumbnail`）
+    pub field: String,
+    pub hash: String,
+    pub url: String,
+}
+
+/// 整合性の問題
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum IntegrityIssue {
+    /// repositoryのファイル名と内容のSHA-1が一致しない
+    HashMismatch { file: String, actual: String },
+    /// SRLのハッシュがrepositoryに無い
+    MissingFile { reference: SrlReference },
+    /// SRLのURLが`/sonolus/repository/<hash>`ではない
+    UrlMismatch { reference: SrlReference, expected: String },
+}
+
+impl fmt::Display for IntegrityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntegrityIssue::HashMismatch { file, actual } => {
+                write!(f, "hash mismatch: repository/{} has SHA-1 {}", file, actual)
+            }
+            IntegrityIssue::MissingFile { reference } => {
+                write!(f, "missing file: {} {} refers to {}", reference.location, reference.field, reference.hash)
+            }
+            IntegrityIssue::UrlMismatch { reference, expected } => {
+                write!(f, "url mismatch: {} {} has url {} (expected {})", reference.location, reference.field, reference.url, expected)
+            }
+        }
+    }
+}
+
+/// 整合性チェックの結果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityReport {
+    pub input: String,
+    pub files_checked: usize,
+    pub references_checked: usize,
+    pub issues: Vec<IntegrityIssue>,
+}
+
+impl IntegrityReport {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl fmt::Display for IntegrityReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Integrity check: {}", self.input)?;
+        writeln!(f, "Checked {} repository files and {} SRL references", self.files_checked, self.references_checked)?;
+
+        for issue in &self.issues {
+            writeln!(f, "  {}", issue)?;
+        }
+
+        if self.is_ok() {
+            write!(f, "OK: no issues")
+        } else {
+            write!(f, "FAILED: {} issues", self.issues.len())
+        }
+    }
+}
+
+/// repositoryのファイルとSRLの整合性
+///
+/// packディレクトリとSCPファイルのどちらからも読み込める
+#[derive(Debug, Clone, Default)]
+pub struct PackIntegrity {
+    /// repositoryのファイル名 → 内容のSHA-1
+    files: BTreeMap<String, String>,
+    references: Vec<SrlReference>,
+}
+
+impl PackIntegrity {
+    /// packディレクトリまたはSCPファイルを読み込む
+    pub fn load(input: &Path) -> Result<Self> {
+        if input.is_dir() {
+            Self::from_pack_dir(input)
+        } else {
+            Self::from_scp(input)
+        }
+    }
+
+    /// packディレクトリのdb.jsonとrepositoryを読み込む
+    pub fn from_pack_dir(pack_dir: &Path) -> Result<Self> {
+        validate_pack_dir(pack_dir)?;
+
+        let mut integrity = Self::default();
+
+        let repo_dir = pack_dir.join("repository");
+        if repo_dir.exists() {
+            for entry in WalkDir::new(&repo_dir).sort_by_file_name() {
+                let entry = entry.map_err(|e| std::io::Error::other(
+                    format!("Failed to read repository entry: {}", e)
+                ))?;
+
+                if entry.path().is_file() {
+                    let name = entry.file_name().to_string_lossy().into_owned();
+                    let hash = sha1_reader(&mut File::open(entry.path())?)?;
+                    integrity.files.insert(name, hash);
+                }
+            }
+        }
+
+        integrity.references = pack_references(pack_dir)?;
+
+        Ok(integrity)
+    }
+
+    /// SCPファイルのrepositoryとJSONエントリを読み込む
+    pub fn from_scp(scp_file: &Path) -> Result<Self> {
+        validate_scp_file(scp_file)?;
+        Self::from_archive(&mut ZipArchive::new(File::open(scp_file)?)?)
+    }
+
+    /// アーカイブのrepositoryとJSONエントリを読み込む
+    ///
+    /// 同じSRLはlistや個別ページに何度も現れるため、最初に見つかった参照元だけを記録する
+    pub fn from_archive<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<Self> {
+        let mut integrity = Self::default();
+        let mut seen = HashSet::new();
+
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            if file.is_dir() {
+                continue;
+            }
+            let path = file.name().to_string();
+
+            if let Some(name) = repository_hash(&path) {
+                integrity.files.insert(name.to_string(), sha1_reader(&mut file)?);
+            } else {
+                let mut buffer = Vec::new();
+                file.read_to_end(&mut buffer)?;
+                if let Ok(value) = serde_json::from_slice::<Value>(&buffer) {
+                    let mut references = Vec::new();
+                    collect_srls(&value, "$".to_string(), &path, &mut references);
+                    for reference in references {
+                        if seen.insert((reference.hash.clone(), reference.url.clone())) {
+                            integrity.references.push(reference);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(integrity)
+    }
+
+    /// repositoryのファイル名と内容のSHA-1
+    pub fn files(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.files.iter().map(|(name, hash)| (name.as_str(), hash.as_str()))
+    }
+
+    /// 見つかったSRL
+    pub fn references(&self) -> &[SrlReference] {
+        &self.references
+    }
+
+    /// ハッシュ・SRLの参照先・URLを検証
+    pub fn check(&self, input: &str) -> IntegrityReport {
+        let mut issues = Vec::new();
+
+        for (name, actual) in &self.files {
+            if name != actual {
+                issues.push(IntegrityIssue::HashMismatch { file: name.clone(), actual: actual.clone() });
+            }
+        }
+
+        for reference in &self.references {
+            if !self.files.contains_key(&reference.hash) {
+                issues.push(IntegrityIssue::MissingFile { reference: reference.clone() });
+            }
+
+            let expected = repository_url(&reference.hash);
+            if reference.url != expected {
+                issues.push(IntegrityIssue::UrlMismatch { reference: reference.clone(), expected });
+            }
+        }
+
+        IntegrityReport {
+            input: input.to_string(),
+            files_checked: self.files.len(),
+            references_checked: self.references.len(),
+            issues,
+        }
+    }
+}
+
+/// packディレクトリのdb.jsonとextraディレクトリのJSONにあるSRL
+pub fn pack_references(pack_dir: &Path) -> Result<Vec<SrlReference>> {
+    let db: Value = serde_json::from_str(&std::fs::read_to_string(pack_dir.join("db.json"))?)?;
+    let mut references = db_references(&db);
+    references.extend(extra_references(pack_dir)?);
+    Ok(references)
+}
+
+/// db.jsonのSRL（参照元はアイテムごとに記録）
+pub fn db_references(db: &Value) -> Vec<SrlReference> {
+    let mut references = Vec::new();
+
+    for (key, value) in db.as_object().into_iter().flatten() {
+        let is_category = ItemType::ALL.iter().any(|ty| ty.db_key() == key);
+        match value.as_array() {
+            Some(items) if is_category => {
+                for (index, item) in items.iter().enumerate() {
+                    let location = match item["name"].as_str() {
+                        Some(name) => format!("{}/{}", key, name),
+                        None => format!("{}[{}]", key, index),
+                    };
+                    collect_srls(item, "$".to_string(), &location, &mut references);
+                }
+            }
+            _ => collect_srls(value, "$".to_string(), key, &mut references),
+        }
+    }
+
+    references
+}
+
+/// extraディレクトリのJSONにあるSRL（extraのエントリもそのままSCPに含まれる）
+pub fn extra_references(pack_dir: &Path) -> Result<Vec<SrlReference>> {
+    let mut references = Vec::new();
+
+    let extra_dir = pack_dir.join(EXTRA_DIR);
+    if extra_dir.exists() {
+        for entry in WalkDir::new(&extra_dir).sort_by_file_name() {
+            let entry = entry.map_err(|e| std::io::Error::other(
+                format!("Failed to read extra entry: {}", e)
+            ))?;
+
+            if entry.path().is_file() {
+                let location = normalize_path(entry.path(), pack_dir)?;
+                references.extend(json_references(&std::fs::read(entry.path())?, &location));
+            }
+        }
+    }
+
+    Ok(references)
+}
+
+/// JSONのエントリにあるSRL（JSONでなければ空）
+pub fn json_references(content: &[u8], location: &str) -> Vec<SrlReference> {
+    let mut references = Vec::new();
+    if let Ok(value) = serde_json::from_slice::<Value>(content) {
+        collect_srls(&value, "$".to_string(), location, &mut references);
+    }
+    references
+}
+
+/// JSONを再帰的に探索し、`hash`と`url`を文字列で持つオブジェクトをSRLとして集める
+fn collect_srls(value: &Value, field: String, location: &str, references: &mut Vec<SrlReference>) {
+    match value {
+        Value::Object(object) => {
+            if let (Some(Value::String(hash)), Some(Value::String(url))) = (object.get("hash"), object.get("url")) {
+                references.push(SrlReference {
+                    location: location.to_string(),
+                    field,
+                    hash: hash.clone(),
+                    url: url.clone(),
+                });
+                return;
+            }
+            for (key, child) in object {
+                collect_srls(child, format!("{}.{}", field, key), location, references);
+            }
+        }
+        Value::Array(items) => {
+            for (index, child) in items.iter().enumerate() {
+                collect_srls(child, format!("{}[{}]", field, index), location, references);
+            }
+        }
+        _ => {}
+    }
+}
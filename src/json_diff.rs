@@ -0,0 +1,95 @@
+use std::fmt;
+use serde::Serialize;
+use serde_json::Value;
+
+/// JSONのフィールド単位の変更（pathは`$.sections[0].title`の形式）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum FieldChange {
+    /// 新しい側にだけあるフィールド
+    Added { path: String, value: Value },
+    /// 元の側にだけあるフィールド
+    Removed { path: String, value: Value },
+    /// 値が変わったフィールド
+    Changed { path: String, old: Value, new: Value },
+}
+
+impl FieldChange {
+    pub fn path(&self) -> &str {
+        match self {
+            FieldChange::Added { path, .. }
+            | FieldChange::Removed { path, .. }
+            | FieldChange::Changed { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FieldChange::Added { path, value } => write!(f, "+ {}: {}", path, preview(value)),
+            FieldChange::Removed { path, value } => write!(f, "- {}: {}", path, preview(value)),
+            FieldChange::Changed { path, old, new } => {
+                write!(f, "~ {}: {} -> {}", path, preview(old), preview(new))
+            }
+        }
+    }
+}
+
+/// 2つのJSONを再帰的に比較し、変更のあったフィールドを列挙
+///
+/// オブジェクトはキーで、配列は要素の位置で対応付ける
+pub fn diff_json(old: &Value, new: &Value) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    diff_value("$".to_string(), old, new, &mut changes);
+    changes
+}
+
+fn diff_value(path: String, old: &Value, new: &Value, changes: &mut Vec<FieldChange>) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, old_value) in old {
+                let child = format!("{}.{}", path, key);
+                match new.get(key) {
+                    Some(new_value) => diff_value(child, old_value, new_value, changes),
+                    None => changes.push(FieldChange::Removed { path: child, value: old_value.clone() }),
+                }
+            }
+            for (key, new_value) in new {
+                if !old.contains_key(key) {
+                    let child = format!("{}.{}", path, key);
+                    changes.push(FieldChange::Added { path: child, value: new_value.clone() });
+                }
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            for index in 0..old.len().max(new.len()) {
+                let child = format!("{}[{}]", path, index);
+                match (old.get(index), new.get(index)) {
+                    (Some(old_value), Some(new_value)) => diff_value(child, old_value, new_value, changes),
+                    (Some(old_value), None) => changes.push(FieldChange::Removed { path: child, value: old_value.clone() }),
+                    (None, Some(new_value)) => changes.push(FieldChange::Added { path: child, value: new_value.clone() }),
+                    (None, None) => {}
+                }
+            }
+        }
+        _ => {
+            if old != new {
+                changes.push(FieldChange::Changed { path, old: old.clone(), new: new.clone() });
+            }
+        }
+    }
+}
+
+/// 表示用に短くしたJSON
+fn preview(value: &Value) -> String {
+    const MAX_CHARS: usize = 80;
+
+    let text = value.to_string();
+    if text.chars().count() > MAX_CHARS {
+        let truncated: String = text.chars().take(MAX_CHARS).collect();
+        format!("{}...", truncated)
+    } else {
+        text
+    }
+}
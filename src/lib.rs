@@ -1,7 +1,23 @@
+pub mod config;
 pub mod converter;
 pub mod pack_extractor;
 pub mod pack_archiver;
+pub mod search;
+pub mod static_tree;
+pub mod verify;
+pub mod json_diff;
+pub mod integrity;
+pub mod gc;
+pub mod select;
+pub mod graph;
+pub mod merge;
+pub mod source;
+pub mod diff;
+pub mod patch;
+pub mod compression;
 pub mod error;
+pub mod locale;
+pub mod model;
 pub mod utils;
 
 pub use converter::Converter;
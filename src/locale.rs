@@ -0,0 +1,82 @@
+use serde_json::Value;
+
+use crate::model::LocalizationText;
+
+/// 多言語テキストから表示する言語を選ぶための優先順位
+///
+/// 指定したロケールを順に探し、どれも無ければ最初に見つかった言語を使う
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocaleFallback {
+    locales: Vec<String>,
+}
+
+impl LocaleFallback {
+    /// 優先順に並べたロケールから作成（例: ["ja", "en"]）
+    pub fn new<I, S>(locales: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut chain: Vec<String> = Vec::new();
+        for locale in locales {
+            let locale = locale.into();
+            if !chain.contains(&locale) {
+                chain.push(locale);
+            }
+        }
+        Self { locales: chain }
+    }
+
+    /// 指定ロケールを先頭にし、残りをフォールバックとして使う
+    pub fn with_primary(&self, locale: &str) -> Self {
+        Self::new(std::iter::once(locale.to_string()).chain(self.locales.iter().cloned()))
+    }
+
+    pub fn locales(&self) -> &[String] {
+        &self.locales
+    }
+
+    /// 優先順位に従ってテキストを1つ選ぶ
+    pub fn resolve(&self, text: &LocalizationText) -> String {
+        self.locales.iter()
+            .find_map(|locale| text.get(locale))
+            .or_else(|| text.0.values().next().map(|s| s.as_str()))
+            .unwrap_or_default()
+            .to_string()
+    }
+}
+
+impl Default for LocaleFallback {
+    fn default() -> Self {
+        Self::new(["en"])
+    }
+}
+
+/// 別ロケールから取り込んだJSONを結合する
+///
+/// 多言語テキストは`{"ja": ...}`と`{"en": ...}`のようにキーが異なるため、
+/// オブジェクトを再帰的に結合すると`{"ja": ..., "en": ...}`になる。
+/// 配列は要素の位置で対応付け、それ以外の値は`base`を優先する。
+pub fn merge_localized(base: &mut Value, other: Value) {
+    match (base, other) {
+        (Value::Object(base), Value::Object(other)) => {
+            for (key, value) in other {
+                match base.get_mut(&key) {
+                    Some(base_value) => merge_localized(base_value, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Array(base), Value::Array(other)) => {
+            for (index, value) in other.into_iter().enumerate() {
+                match base.get_mut(index) {
+                    Some(base_value) => merge_localized(base_value, value),
+                    None => base.push(value),
+                }
+            }
+        }
+        _ => {}
+    }
+}
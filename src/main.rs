@@ -1,6 +1,15 @@
+use std::fmt::Display;
 use std::path::PathBuf;
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap::error::ErrorKind;
+use serde::Serialize;
 use scp_pack::Converter;
+use scp_pack::compression::{Compression, CompressionRule};
+use scp_pack::locale::LocaleFallback;
+use scp_pack::merge::ConflictPolicy;
+use scp_pack::model::ItemType;
+use scp_pack::select::ItemPattern;
+use scp_pack::utils::locale_from_path;
 
 #[derive(Parser)]
 #[command(name = "scp-pack")]
@@ -10,9 +19,21 @@ struct Cli {
     #[command(subcommand)]
     command: Commands,
     
-    /// Compression level (0-9, higher = better compression)
-    #[arg(short, long, default_value = "6")]
-    compression: i64,
+    /// Compression method for entries that are not already compressed (stored, deflate, bzip2, zstd)
+    #[arg(short, long, default_value_t = Compression::default())]
+    method: Compression,
+    
+    /// Compression level for --method (deflate: 0-9 with 0 = stored, bzip2: 1-9, zstd: 1-22; higher = better compression)
+    #[arg(short, long)]
+    compression: Option<i64>,
+    
+    /// Compression for entries matching a path pattern, overriding content sniffing (e.g. "static/sonolus/repository/*=deflate"; repeatable)
+    #[arg(long, value_name = "PATTERN=METHOD")]
+    compression_rule: Vec<CompressionRule>,
+    
+    /// Number of threads used to compress entries (default: number of CPUs; output is the same for any value)
+    #[arg(short, long, value_parser = clap::value_parser!(u64).range(1..))]
+    jobs: Option<u64>,
 }
 
 #[derive(Subcommand)]
@@ -26,22 +47,70 @@ enum Commands {
         /// Output SCP file
         #[arg(short, long)]
         output: PathBuf,
+        
+        /// Locale used for text in the static files
+        #[arg(short, long, default_value = "en")]
+        locale: String,
+        
+        /// Fallback locales tried in order when text is missing in --locale
+        #[arg(long, value_delimiter = ',', default_value = "en")]
+        fallback: Vec<String>,
+        
+        /// Emit one SCP per locale (e.g. ja,en,zh-hans → output.ja.scp, ...)
+        #[arg(long, value_delimiter = ',')]
+        per_locale: Vec<String>,
+        
+        /// Items per list page (default: all items on one page)
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+        page_size: Option<u64>,
+        
+        /// Set shouldUpdate in static/sonolus/package (overrides db.json)
+        #[arg(long)]
+        should_update: Option<bool>,
+        
+        /// Only include repository files referenced by an SRL in db.json
+        #[arg(long)]
+        reachable_only: bool,
+        
+        /// Only pack these items and what they reference (e.g. levels/my-level, levels/chapter1-*; repeatable)
+        #[arg(long, value_name = "CATEGORY/NAME")]
+        include: Vec<ItemPattern>,
+        
+        /// Produce byte-identical output for the same input (sorted entries, fixed timestamps from SOURCE_DATE_EPOCH or 1980-01-01, fixed permissions)
+        #[arg(long)]
+        deterministic: bool,
     },
     /// Convert SCP file to pack directory  
     Unpack {
-        /// Input SCP file
-        #[arg(short, long)]
-        input: PathBuf,
+        /// Input SCP file (repeat with --merge-locale)
+        #[arg(short, long, required = true)]
+        input: Vec<PathBuf>,
         
         /// Output pack directory
         #[arg(short, long)]
         output: PathBuf,
+        
+        /// Locale of the text in the SCP (repeat with --merge-locale, in input order)
+        #[arg(short, long)]
+        locale: Vec<String>,
+        
+        /// Merge several per-locale SCPs of the same pack into one multi-locale db.json
+        #[arg(long)]
+        merge_locale: bool,
+        
+        /// Keep item pages, category info and unknown entries so that repacking reproduces the SCP
+        #[arg(long, conflicts_with = "merge_locale")]
+        lossless: bool,
     },
     /// List contents of SCP file
     List {
         /// SCP file to list
         #[arg(short, long)]
         file: PathBuf,
+        
+        /// Only list entries of this item type (e.g. level, skins)
+        #[arg(short = 't', long = "type")]
+        item_type: Option<ItemType>,
     },
     /// Show content of specific file in SCP
     Show {
@@ -53,26 +122,280 @@ enum Commands {
         #[arg(short, long)]
         file: String,
     },
+    /// Convert a pack directory or SCP file both ways in memory and report every difference
+    /// (exit code 0: identical, 1: error, 2: differences found)
+    VerifyRoundtrip {
+        /// Input pack directory or SCP file
+        #[arg(short, long)]
+        input: PathBuf,
+        
+        /// Locale used for text on both sides of the conversion
+        #[arg(short, long, default_value = "en")]
+        locale: String,
+        
+        /// Unpack in lossless mode (see `unpack --lossless`)
+        #[arg(long)]
+        lossless: bool,
+        
+        /// Report format
+        #[arg(long, value_enum, default_value = "text")]
+        format: ReportFormat,
+    },
+    /// Check repository files against their SHA-1 names and every SRL in the pack
+    /// (exit code 0: no issues, 1: error, 2: issues found)
+    Check {
+        /// Input pack directory or SCP file
+        #[arg(short, long)]
+        input: PathBuf,
+        
+        /// Report format
+        #[arg(long, value_enum, default_value = "text")]
+        format: ReportFormat,
+    },
+    /// List repository files of a pack directory that no SRL refers to
+    Gc {
+        /// Pack directory
+        #[arg(short, long)]
+        input: PathBuf,
+        
+        /// Delete the unreferenced files instead of only listing them
+        #[arg(long)]
+        delete: bool,
+        
+        /// Report format
+        #[arg(long, value_enum, default_value = "text")]
+        format: ReportFormat,
+    },
+    /// Build the item dependency graph of a pack directory and report dangling or mismatched references
+    /// (exit code 0: no issues, 1: error, 2: issues found)
+    Graph {
+        /// Pack directory
+        #[arg(short, long)]
+        input: PathBuf,
+        
+        /// Output format
+        #[arg(long, value_enum, default_value = "text")]
+        format: GraphFormat,
+        
+        /// Write the graph to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Compare two pack directories or SCP files item by item
+    /// (exit code 0: identical, 1: error, 2: differences found)
+    Diff {
+        /// Old pack directory or SCP file
+        old: PathBuf,
+        
+        /// New pack directory or SCP file
+        new: PathBuf,
+        
+        /// Locale of the text in SCP inputs
+        #[arg(short, long, default_value = "en")]
+        locale: String,
+        
+        /// Report format
+        #[arg(long, value_enum, default_value = "text")]
+        format: ReportFormat,
+    },
+    /// Create a patch that turns OLD into NEW, holding only new and changed entries
+    MakePatch {
+        /// SCP file the patch applies to
+        old: PathBuf,
+        
+        /// SCP file the patch produces
+        new: PathBuf,
+        
+        /// Output patch file (e.g. update.scppatch)
+        #[arg(short, long)]
+        output: PathBuf,
+        
+        /// Report format
+        #[arg(long, value_enum, default_value = "text")]
+        format: ReportFormat,
+    },
+    /// Apply a patch created by make-patch and rebuild the new SCP byte for byte
+    ApplyPatch {
+        /// SCP file the patch was made from (refused if its checksum differs)
+        base: PathBuf,
+        
+        /// Patch file
+        patch: PathBuf,
+        
+        /// Output SCP file
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Merge several pack directories or SCP files into one, deduplicating repository files by hash
+    /// (exit code 0: merged, 1: error, 2: unresolved conflicts, nothing written)
+    Merge {
+        /// Input pack directories or SCP files (repeatable, earlier inputs come first)
+        #[arg(short, long, required = true, num_args = 1..)]
+        input: Vec<PathBuf>,
+        
+        /// Output SCP file (*.scp) or pack directory
+        #[arg(short, long)]
+        output: PathBuf,
+        
+        /// Locale of the text in SCP inputs and of the SCP output
+        #[arg(short, long, default_value = "en")]
+        locale: String,
+        
+        /// Unpack SCP inputs in lossless mode (see `unpack --lossless`)
+        #[arg(long)]
+        lossless: bool,
+        
+        /// How to resolve items with the same name: error, highest-version, first, last or rename
+        #[arg(long, default_value = "error")]
+        on_conflict: ConflictPolicy,
+        
+        /// Prefix for renamed items ({input}: input directory name, {index}: input number)
+        #[arg(long, default_value = "{input}-")]
+        prefix: String,
+        
+        /// Report format
+        #[arg(long, value_enum, default_value = "text")]
+        format: ReportFormat,
+    },
+}
+
+/// 依存関係グラフの出力形式
+#[derive(Clone, Copy, ValueEnum)]
+enum GraphFormat {
+    /// Issues only
+    Text,
+    /// Graphviz
+    Dot,
+    Json,
+}
+
+/// 検証結果などの出力形式
+#[derive(Clone, Copy, ValueEnum)]
+enum ReportFormat {
+    Text,
+    Json,
 }
 
 fn main() {
     let cli = Cli::parse();
     
-    let converter = Converter::new().with_compression_level(cli.compression);
+    if let Some(level) = cli.compression {
+        if let Err(message) = cli.method.check_level(level) {
+            Cli::command().error(ErrorKind::ValueValidation, message).exit();
+        }
+    }
+    
+    let converter = Converter::new()
+        .with_compression(cli.method, cli.compression)
+        .with_compression_overrides(cli.compression_rule)
+        .with_jobs(cli.jobs.map(|jobs| jobs as usize));
     
     let result = match cli.command {
-        Commands::Pack { input, output } => {
-            converter.pack_to_scp(&input, &output)
+        Commands::Pack { input, output, locale, fallback, per_locale, page_size, should_update, reachable_only, include, deterministic } => {
+            let source_date_epoch = match deterministic.then(source_date_epoch).transpose() {
+                Ok(epoch) => epoch.flatten(),
+                Err(message) => Cli::command().error(ErrorKind::InvalidValue, message).exit(),
+            };
+            let fallback = LocaleFallback::new(fallback);
+            let converter = converter
+                .with_deterministic(deterministic, source_date_epoch)
+                .with_page_size(page_size.map(|size| size as usize))
+                .with_should_update(should_update)
+                .with_reachable_only(reachable_only)
+                .with_includes(include);
+            
+            if per_locale.is_empty() {
+                converter.with_locale(fallback.with_primary(&locale))
+                    .pack_to_scp(&input, &output)
+            } else {
+                converter.with_locale(fallback)
+                    .pack_to_scp_per_locale(&input, &output, &per_locale)
+            }
         },
-        Commands::Unpack { input, output } => {
-            converter.scp_to_pack(&input, &output)
+        Commands::Unpack { input, output, locale, merge_locale, lossless } => {
+            if merge_locale {
+                let scp_files = match pair_input_locales(input, locale) {
+                    Ok(scp_files) => scp_files,
+                    Err(message) => Cli::command().error(ErrorKind::ArgumentConflict, message).exit(),
+                };
+                converter.merge_locale_scps_to_pack(&scp_files, &output)
+            } else {
+                if input.len() > 1 || locale.len() > 1 {
+                    Cli::command()
+                        .error(ErrorKind::ArgumentConflict, "multiple --input/--locale values require --merge-locale")
+                        .exit();
+                }
+                let locale = locale.first().map(|s| s.as_str()).unwrap_or("en");
+                converter.with_extract_locale(locale)
+                    .with_lossless(lossless)
+                    .scp_to_pack(&input[0], &output)
+            }
         },
-        Commands::List { file } => {
-            converter.list_scp_contents(&file)
+        Commands::List { file, item_type } => {
+            match item_type {
+                Some(item_type) => converter.list_category_contents(&file, item_type),
+                None => converter.list_scp_contents(&file),
+            }
         },
         Commands::Show { scp, file } => {
             converter.show_file(&scp, &file)
         },
+        Commands::VerifyRoundtrip { input, locale, lossless, format } => {
+            converter.with_locale(LocaleFallback::new([locale.clone()]))
+                .with_extract_locale(&locale)
+                .with_lossless(lossless)
+                .verify_roundtrip(&input)
+                .and_then(|report| print_report(&report, format, report.is_lossless()))
+        },
+        Commands::Check { input, format } => {
+            converter.check_integrity(&input)
+                .and_then(|report| print_report(&report, format, report.is_ok()))
+        },
+        Commands::Graph { input, format, output } => {
+            converter.dependency_graph(&input)
+                .and_then(|graph| {
+                    let content = match format {
+                        GraphFormat::Text => graph.to_string(),
+                        GraphFormat::Dot => graph.to_dot(),
+                        GraphFormat::Json => serde_json::to_string_pretty(&graph)?,
+                    };
+                    match &output {
+                        Some(path) => {
+                            std::fs::write(path, content)?;
+                            println!("{}", graph);
+                        }
+                        None => println!("{}", content.trim_end()),
+                    }
+                    if !graph.is_ok() {
+                        std::process::exit(2);
+                    }
+                    Ok(())
+                })
+        },
+        Commands::Diff { old, new, locale, format } => {
+            converter.with_extract_locale(&locale)
+                .diff(&old, &new)
+                .and_then(|diff| print_report(&diff, format, diff.is_empty()))
+        },
+        Commands::MakePatch { old, new, output, format } => {
+            converter.make_patch(&old, &new, &output)
+                .and_then(|report| print_report(&report, format, true))
+        },
+        Commands::ApplyPatch { base, patch, output } => {
+            converter.apply_patch(&base, &patch, &output)
+        },
+        Commands::Merge { input, output, locale, lossless, on_conflict, prefix, format } => {
+            let converter = converter
+                .with_locale(LocaleFallback::new([locale.clone()]))
+                .with_extract_locale(&locale)
+                .with_lossless(lossless);
+            converter.merge_packs(&input, &output, on_conflict, Some(prefix))
+                .and_then(|report| print_report(&report, format, report.is_ok()))
+        },
+        Commands::Gc { input, delete, format } => {
+            converter.gc_repository(&input, delete)
+                .and_then(|report| print_report(&report, format, true))
+        },
     };
     
     if let Err(e) = result {
@@ -80,3 +403,47 @@ fn main() {
         std::process::exit(1);
     }
 }
+
+/// レポートを出力し、問題が見つかった場合は終了コード2で終了
+fn print_report<R: Display + Serialize>(report: &R, format: ReportFormat, ok: bool) -> scp_pack::error::Result<()> {
+    match format {
+        ReportFormat::Text => println!("{}", report),
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(report)?),
+    }
+    if !ok {
+        std::process::exit(2);
+    }
+    Ok(())
+}
+
+/// 環境変数`SOURCE_DATE_EPOCH`を読む（未設定ならNone）
+fn source_date_epoch() -> Result<Option<i64>, String> {
+    match std::env::var("SOURCE_DATE_EPOCH") {
+        Ok(value) => value.trim().parse()
+            .map(Some)
+            .map_err(|_| format!("SOURCE_DATE_EPOCH must be an integer number of seconds, got {:?}", value)),
+        Err(_) => Ok(None),
+    }
+}
+
+/// --merge-locale用に入力ファイルとロケールを対応付ける
+/// （--localeが無い場合はoutput.ja.scpのようなファイル名から推測）
+fn pair_input_locales(input: Vec<PathBuf>, locale: Vec<String>) -> Result<Vec<(PathBuf, String)>, String> {
+    if !locale.is_empty() {
+        if locale.len() != input.len() {
+            return Err(format!(
+                "--merge-locale needs one --locale per --input ({} inputs, {} locales)",
+                input.len(),
+                locale.len()
+            ));
+        }
+        return Ok(input.into_iter().zip(locale).collect());
+    }
+    
+    input.into_iter()
+        .map(|path| match locale_from_path(&path) {
+            Some(locale) => Ok((path, locale)),
+            None => Err(format!("cannot infer locale from {}; pass --locale", path.display())),
+        })
+        .collect()
+}
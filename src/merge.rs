@@ -0,0 +1,554 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+use std::str::FromStr;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::Result;
+use crate::model::{Item, ItemType, PackDatabase};
+use crate::pack_archiver::PackArchiver;
+use crate::pack_extractor::PackExtractor;
+use crate::source::PackSource;
+use crate::static_tree::EntryData;
+use crate::utils::{prepare_output_dir, EXTRA_DIR};
+
+/// 名前が衝突したアイテムの扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictPolicy {
+    /// 衝突があれば結合しない
+    #[default]
+    Error,
+    /// `version`が大きい方を残す（同じなら先の入力）
+    HighestVersion,
+    /// 先の入力のアイテムを残す
+    First,
+    /// 後の入力のアイテムで置き換える
+    Last,
+    /// 後の入力のアイテムの名前に接頭辞を付ける
+    Rename,
+}
+
+impl FromStr for ConflictPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(ConflictPolicy::Error),
+            "highest-version" => Ok(ConflictPolicy::HighestVersion),
+            "first" => Ok(ConflictPolicy::First),
+            "last" => Ok(ConflictPolicy::Last),
+            "rename" => Ok(ConflictPolicy::Rename),
+            _ => Err(format!(
+                "Unknown conflict policy: {} (expected error, highest-version, first, last or rename)", s
+            )),
+        }
+    }
+}
+
+/// 衝突の解決方法
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Resolution {
+    /// 解決できなかった（出力しない）
+    Unresolved,
+    /// 先の入力の内容を残した
+    KeptExisting,
+    /// 後の入力の内容で置き換えた
+    Replaced,
+    /// 後の入力のアイテムを改名した
+    Renamed { name: String },
+}
+
+/// 結合時の衝突
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeConflict {
+    /// 衝突した対象（例: `skins/pixel`、`info`、`repository/<hash>`）
+    pub subject: String,
+    /// 先に取り込んだ入力
+    pub existing: String,
+    /// 後から取り込んだ入力
+    pub incoming: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub existing_version: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub incoming_version: Option<u32>,
+    pub resolution: Resolution,
+}
+
+impl fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {} vs {}", self.subject, self.existing, self.incoming)?;
+        if let (Some(existing), Some(incoming)) = (self.existing_version, self.incoming_version) {
+            write!(f, " (version {} vs {})", existing, incoming)?;
+        }
+        match &self.resolution {
+            Resolution::Unresolved => write!(f, " -> unresolved"),
+            Resolution::KeptExisting => write!(f, " -> kept {}", self.existing),
+            Resolution::Replaced => write!(f, " -> replaced by {}", self.incoming),
+            Resolution::Renamed { name } => write!(f, " -> renamed to {}", name),
+        }
+    }
+}
+
+/// 結合の結果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeReport {
+    pub inputs: Vec<String>,
+    pub output: String,
+    pub policy: ConflictPolicy,
+    pub items: usize,
+    pub repository_files: usize,
+    /// 複数の入力にあった同じハッシュのファイル数
+    pub deduplicated_files: usize,
+    pub deduplicated_bytes: u64,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl MergeReport {
+    /// 解決できない衝突が無いか
+    pub fn is_ok(&self) -> bool {
+        self.conflicts.iter().all(|conflict| conflict.resolution != Resolution::Unresolved)
+    }
+
+    pub fn unresolved(&self) -> usize {
+        self.conflicts.iter().filter(|conflict| conflict.resolution == Resolution::Unresolved).count()
+    }
+}
+
+impl fmt::Display for MergeReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Merge: {} -> {}", self.inputs.join(", "), self.output)?;
+
+        for conflict in &self.conflicts {
+            writeln!(f, "  {}", conflict)?;
+        }
+
+        writeln!(
+            f,
+            "{} items, {} repository files ({} duplicates, {} bytes deduplicated)",
+            self.items, self.repository_files, self.deduplicated_files, self.deduplicated_bytes
+        )?;
+
+        if self.is_ok() {
+            write!(f, "OK: {} conflicts resolved", self.conflicts.len())
+        } else {
+            write!(f, "FAILED: {} unresolved conflicts (nothing written)", self.unresolved())
+        }
+    }
+}
+
+/// 複数のpack（packディレクトリ・SCPファイル）を1つに結合する
+///
+/// アイテムの名前の衝突は`ConflictPolicy`に従って解決する。内容が同じアイテムは衝突として扱わない。
+/// `info`などアイテム以外の設定が異なる場合は先の入力を残し（`Last`では後の入力）、衝突として報告する。
+#[derive(Debug, Clone, Default)]
+pub struct PackMerger {
+    policy: ConflictPolicy,
+    rename_prefix: Option<String>,
+}
+
+impl PackMerger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_policy(mut self, policy: ConflictPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// `Rename`で付ける接頭辞（`{input}`は入力名、`{index}`は入力の番号に置き換える。既定は`{input}-`）
+    pub fn with_rename_prefix(mut self, prefix: Option<String>) -> Self {
+        self.rename_prefix = prefix;
+        self
+    }
+
+    /// packディレクトリ・SCPファイルを結合して書き込み（解決できない衝突があれば書き込まない）
+    ///
+    /// 出力の拡張子が`.scp`ならSCPファイル、それ以外はpackディレクトリに書き込む。
+    /// SCPファイルの入力・出力は、`unpack`・`merge`・`pack`を順に行った結果と同じ内容になる
+    pub fn merge_inputs(
+        &self,
+        extractor: &PackExtractor,
+        archiver: &PackArchiver,
+        inputs: &[impl AsRef<Path>],
+        output: &Path,
+    ) -> Result<MergeReport> {
+        let sources = inputs.iter()
+            .map(|input| PackSource::load(extractor, input.as_ref()))
+            .collect::<Result<Vec<_>>>()?;
+
+        let (merged, report) = self.merge(sources, &output.display().to_string())?;
+        if !report.is_ok() {
+            return Ok(report);
+        }
+
+        if output.extension().and_then(|s| s.to_str()) == Some("scp") {
+            if let Some(parent) = output.parent() {
+                prepare_output_dir(parent)?;
+            }
+            let tree = archiver.build_source(merged)?;
+            archiver.write_archive(&tree, File::create(output)?)?;
+        } else {
+            merged.write(output)?;
+        }
+        Ok(report)
+    }
+
+    /// 入力をメモリ上で結合（結果の入力名は`output`）
+    pub fn merge(&self, sources: Vec<PackSource>, output: &str) -> Result<(PackSource, MergeReport)> {
+        let mut state = MergeState {
+            policy: self.policy,
+            labels: sources.iter().map(|source| source.label.clone()).collect(),
+            db: PackDatabase::default(),
+            origins: HashMap::new(),
+            conflicts: Vec::new(),
+        };
+        let mut settings: Vec<(PackDatabase, usize)> = Vec::new();
+        let mut repository = FileMerge::default();
+        let mut extra = FileMerge::default();
+
+        for (index, mut source) in sources.into_iter().enumerate() {
+            if self.policy == ConflictPolicy::Rename && index > 0 {
+                let prefix = self.rename_prefix(&source.label, index);
+                state.rename_conflicting(&mut source.db, &prefix, index)?;
+            }
+
+            state.merge_items(index, &mut source.db)?;
+            settings.push((source.db, index));
+
+            for (name, data) in source.repository {
+                repository.add(&mut state, format!("repository/{}", name), name, data, index)?;
+            }
+            for (path, data) in source.extra {
+                extra.add(&mut state, format!("{}/{}", EXTRA_DIR, path), path, data, index)?;
+            }
+        }
+
+        state.merge_settings(&settings)?;
+
+        let report = MergeReport {
+            inputs: state.labels.clone(),
+            output: output.to_string(),
+            policy: self.policy,
+            items: state.origins.len(),
+            repository_files: repository.files.len(),
+            deduplicated_files: repository.duplicates,
+            deduplicated_bytes: repository.duplicate_bytes,
+            conflicts: state.conflicts,
+        };
+        let merged = PackSource {
+            label: output.to_string(),
+            db: state.db,
+            repository: repository.into_files(),
+            extra: extra.into_files(),
+        };
+
+        Ok((merged, report))
+    }
+
+    fn rename_prefix(&self, label: &str, index: usize) -> String {
+        let input = Path::new(label)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| label.to_string());
+
+        self.rename_prefix.as_deref()
+            .unwrap_or("{input}-")
+            .replace("{input}", &input)
+            .replace("{index}", &(index + 1).to_string())
+    }
+}
+
+/// 結合途中の状態
+struct MergeState {
+    policy: ConflictPolicy,
+    labels: Vec<String>,
+    db: PackDatabase,
+    /// 結合済みのアイテム → 取り込んだ入力
+    origins: HashMap<(ItemType, String), usize>,
+    conflicts: Vec<MergeConflict>,
+}
+
+impl MergeState {
+    /// 結合済みのアイテムと名前が同じで内容が異なるアイテムを改名（名前での参照も書き換える）
+    ///
+    /// 参照先の改名で内容が変わったアイテムも改名するため、改名が無くなるまで繰り返す
+    fn rename_conflicting(&mut self, incoming: &mut PackDatabase, prefix: &str, index: usize) -> Result<()> {
+        loop {
+            let mut renamed = false;
+
+            for item_type in ItemType::ALL {
+                let mut conflicting = Vec::new();
+                for item in incoming.items(item_type) {
+                    if self.differs(item_type, item)? {
+                        conflicting.push(item.name().to_string());
+                    }
+                }
+
+                for name in conflicting {
+                    let new_name = format!("{}{}", prefix, name);
+                    let taken = self.origins.contains_key(&(item_type, new_name.clone()))
+                        || incoming.items(item_type).iter().any(|item| item.name() == new_name);
+                    if taken {
+                        // 改名できなければそのまま残し、結合時に未解決として報告する
+                        continue;
+                    }
+
+                    incoming.rename_item(item_type, &name, &new_name);
+                    self.conflicts.push(MergeConflict {
+                        subject: format!("{}/{}", item_type.path(), name),
+                        existing: self.origin_label(item_type, &name),
+                        incoming: self.labels[index].clone(),
+                        existing_version: None,
+                        incoming_version: None,
+                        resolution: Resolution::Renamed { name: new_name },
+                    });
+                    renamed = true;
+                }
+            }
+
+            if !renamed {
+                return Ok(());
+            }
+        }
+    }
+
+    /// 結合済みの同名アイテムと内容が異なるか
+    fn differs(&self, item_type: ItemType, item: &dyn Item) -> Result<bool> {
+        match self.db.items(item_type).into_iter().find(|existing| existing.name() == item.name()) {
+            Some(existing) => Ok(existing.to_value()? != item.to_value()?),
+            None => Ok(false),
+        }
+    }
+
+    fn origin_label(&self, item_type: ItemType, name: &str) -> String {
+        self.origins.get(&(item_type, name.to_string()))
+            .map(|index| self.labels[*index].clone())
+            .unwrap_or_default()
+    }
+
+    fn merge_items(&mut self, index: usize, incoming: &mut PackDatabase) -> Result<()> {
+        let mut db = std::mem::take(&mut self.db);
+
+        self.merge_category(ItemType::Skin, &mut db.skins, std::mem::take(&mut incoming.skins), index)?;
+        self.merge_category(ItemType::Background, &mut db.backgrounds, std::mem::take(&mut incoming.backgrounds), index)?;
+        self.merge_category(ItemType::Effect, &mut db.effects, std::mem::take(&mut incoming.effects), index)?;
+        self.merge_category(ItemType::Particle, &mut db.particles, std::mem::take(&mut incoming.particles), index)?;
+        self.merge_category(ItemType::Engine, &mut db.engines, std::mem::take(&mut incoming.engines), index)?;
+        self.merge_category(ItemType::Level, &mut db.levels, std::mem::take(&mut incoming.levels), index)?;
+        self.merge_category(ItemType::Replay, &mut db.replays, std::mem::take(&mut incoming.replays), index)?;
+        self.merge_category(ItemType::Playlist, &mut db.playlists, std::mem::take(&mut incoming.playlists), index)?;
+        self.merge_category(ItemType::Post, &mut db.posts, std::mem::take(&mut incoming.posts), index)?;
+
+        self.db = db;
+        Ok(())
+    }
+
+    fn merge_category<I: Item>(
+        &mut self,
+        item_type: ItemType,
+        merged: &mut Vec<I>,
+        incoming: Vec<I>,
+        index: usize,
+    ) -> Result<()> {
+        for item in incoming {
+            let key = (item_type, item.name().to_string());
+            let Some(position) = merged.iter().position(|existing| existing.name() == item.name()) else {
+                self.origins.insert(key, index);
+                merged.push(item);
+                continue;
+            };
+
+            let existing = &merged[position];
+            if existing.to_value()? == item.to_value()? {
+                continue;
+            }
+
+            let resolution = match self.policy {
+                ConflictPolicy::Error | ConflictPolicy::Rename => Resolution::Unresolved,
+                ConflictPolicy::First => Resolution::KeptExisting,
+                ConflictPolicy::Last => Resolution::Replaced,
+                ConflictPolicy::HighestVersion if item.version() > existing.version() => Resolution::Replaced,
+                ConflictPolicy::HighestVersion => Resolution::KeptExisting,
+            };
+
+            self.conflicts.push(MergeConflict {
+                subject: format!("{}/{}", item_type.path(), item.name()),
+                existing: self.labels[self.origins[&key]].clone(),
+                incoming: self.labels[index].clone(),
+                existing_version: Some(existing.version()),
+                incoming_version: Some(item.version()),
+                resolution: resolution.clone(),
+            });
+
+            if resolution == Resolution::Replaced {
+                self.origins.insert(key, index);
+                merged[position] = item;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// アイテム以外の設定を結合
+    ///
+    /// おすすめと個別ページの上書きは残ったアイテムの入力から、`omit`は全入力から取る
+    fn merge_settings(&mut self, sources: &[(PackDatabase, usize)]) -> Result<()> {
+        let defaults = setting_values(&PackDatabase::default())?;
+        let mut settings: BTreeMap<String, (Value, usize)> = BTreeMap::new();
+
+        for (db, index) in sources {
+            for (key, value) in setting_values(db)? {
+                if defaults.get(&key) != Some(&value) {
+                    self.merge_setting(&mut settings, key, value, *index);
+                }
+            }
+        }
+
+        let mut object = defaults;
+        object.extend(settings.into_iter().map(|(key, (value, _))| (key, value)));
+
+        let mut merged = std::mem::take(&mut self.db);
+        merged.info = serde_json::from_value(object.remove("info").unwrap_or_default())?;
+        merged.package = serde_json::from_value(object.remove("package").unwrap_or_default())?;
+        merged.level_result_info = serde_json::from_value(object.remove("levelResultInfo").unwrap_or_default())?;
+        merged.config = serde_json::from_value(object.remove("config").unwrap_or_default())?;
+        for (key, value) in object {
+            match key.strip_prefix(CATEGORY_INFO_PREFIX).map(str::parse::<ItemType>) {
+                Some(Ok(item_type)) => {
+                    merged.overrides.category_info.insert(item_type, value);
+                }
+                _ => {
+                    merged.extra.insert(key, value);
+                }
+            }
+        }
+
+        for ((item_type, name), index) in &self.origins {
+            let db = &sources[*index].0;
+            if let Some(names) = db.config.recommendations.get(item_type).and_then(|items| items.get(name)) {
+                merged.config.recommendations.entry(*item_type).or_default().insert(name.clone(), names.clone());
+            }
+            if let Some(page) = db.overrides.item_page(*item_type, name) {
+                merged.overrides.item_pages.entry(*item_type).or_default().insert(name.clone(), page.clone());
+            }
+        }
+        for (db, _) in sources {
+            for path in &db.overrides.omit {
+                if !merged.overrides.omit.contains(path) {
+                    merged.overrides.omit.push(path.clone());
+                }
+            }
+        }
+
+        self.db = merged;
+        Ok(())
+    }
+
+    fn merge_setting(&mut self, settings: &mut BTreeMap<String, (Value, usize)>, key: String, value: Value, index: usize) {
+        let Some((existing, origin)) = settings.get(&key) else {
+            settings.insert(key, (value, index));
+            return;
+        };
+        if *existing == value {
+            return;
+        }
+
+        let resolution = self.keep_or_replace();
+        self.conflicts.push(MergeConflict {
+            subject: key.clone(),
+            existing: self.labels[*origin].clone(),
+            incoming: self.labels[index].clone(),
+            existing_version: None,
+            incoming_version: None,
+            resolution: resolution.clone(),
+        });
+        if resolution == Resolution::Replaced {
+            settings.insert(key, (value, index));
+        }
+    }
+
+    /// アイテム以外の衝突は`Last`なら置き換え、それ以外は先の入力を残す
+    fn keep_or_replace(&self) -> Resolution {
+        if self.policy == ConflictPolicy::Last {
+            Resolution::Replaced
+        } else {
+            Resolution::KeptExisting
+        }
+    }
+}
+
+/// repository・extraディレクトリのファイルの結合（同じ名前は1つにまとめる）
+#[derive(Default)]
+struct FileMerge {
+    files: Vec<(String, EntryData)>,
+    index: HashMap<String, (usize, usize)>,
+    duplicates: usize,
+    duplicate_bytes: u64,
+}
+
+impl FileMerge {
+    fn add(&mut self, state: &mut MergeState, subject: String, name: String, data: EntryData, source: usize) -> Result<()> {
+        let Some((position, origin)) = self.index.get(&name).copied() else {
+            self.index.insert(name.clone(), (self.files.len(), source));
+            self.files.push((name, data));
+            return Ok(());
+        };
+
+        let content = data.read()?;
+        if *self.files[position].1.read()? == *content {
+            self.duplicates += 1;
+            self.duplicate_bytes += content.len() as u64;
+            return Ok(());
+        }
+
+        let resolution = state.keep_or_replace();
+        state.conflicts.push(MergeConflict {
+            subject,
+            existing: state.labels[origin].clone(),
+            incoming: state.labels[source].clone(),
+            existing_version: None,
+            incoming_version: None,
+            resolution: resolution.clone(),
+        });
+        if resolution == Resolution::Replaced {
+            self.index.insert(name, (position, source));
+            self.files[position].1 = EntryData::Bytes(content.into_owned());
+        }
+
+        Ok(())
+    }
+
+    fn into_files(self) -> Vec<(String, EntryData)> {
+        self.files
+    }
+}
+
+const CATEGORY_INFO_PREFIX: &str = "overrides.categoryInfo.";
+
+/// アイテム以外の設定（おすすめ・個別ページの上書き・`omit`を除く）
+fn setting_values(db: &PackDatabase) -> Result<BTreeMap<String, Value>> {
+    let mut config = db.config.clone();
+    config.recommendations.clear();
+
+    let mut values = BTreeMap::from([
+        ("info".to_string(), serde_json::to_value(&db.info)?),
+        ("package".to_string(), serde_json::to_value(&db.package)?),
+        ("levelResultInfo".to_string(), serde_json::to_value(&db.level_result_info)?),
+        ("config".to_string(), serde_json::to_value(config)?),
+    ]);
+    for (item_type, info) in &db.overrides.category_info {
+        values.insert(format!("{}{}", CATEGORY_INFO_PREFIX, item_type.path()), info.clone());
+    }
+    for (key, value) in &db.extra {
+        values.insert(key.clone(), value.clone());
+    }
+
+    Ok(values)
+}
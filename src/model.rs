@@ -0,0 +1,1151 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Number, Value};
+
+use crate::config::PackConfig;
+use crate::error::{Result, ScpError};
+
+/// アイテムのカテゴリ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ItemType {
+    Skin,
+    Background,
+    Effect,
+    Particle,
+    Engine,
+    Level,
+    Replay,
+    Playlist,
+    Post,
+}
+
+impl ItemType {
+    /// 全カテゴリ（アーカイブ時の処理順）
+    pub const ALL: [ItemType; 9] = [
+        ItemType::Skin,
+        ItemType::Background,
+        ItemType::Effect,
+        ItemType::Particle,
+        ItemType::Engine,
+        ItemType::Level,
+        ItemType::Replay,
+        ItemType::Playlist,
+        ItemType::Post,
+    ];
+
+    /// 静的ファイルのパスに使う複数形（例: "skins"）
+    pub fn path(&self) -> &'static str {
+        match self {
+            ItemType::Skin => "skins",
+            ItemType::Background => "backgrounds",
+            ItemType::Effect => "effects",
+            ItemType::Particle => "particles",
+            ItemType::Engine => "engines",
+            ItemType::Level => "levels",
+            ItemType::Replay => "replays",
+            ItemType::Playlist => "playlists",
+            ItemType::Post => "posts",
+        }
+    }
+
+    /// 静的ファイル内の`itemType`に使う単数形（例: "skin"）
+    pub fn item_type(&self) -> &'static str {
+        match self {
+            ItemType::Skin => "skin",
+            ItemType::Background => "background",
+            ItemType::Effect => "effect",
+            ItemType::Particle => "particle",
+            ItemType::Engine => "engine",
+            ItemType::Level => "level",
+            ItemType::Replay => "replay",
+            ItemType::Playlist => "playlist",
+            ItemType::Post => "post",
+        }
+    }
+
+    /// db.jsonのキー（例: "skins"）
+    pub fn db_key(&self) -> &'static str {
+        self.path()
+    }
+}
+
+impl std::fmt::Display for ItemType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.item_type())
+    }
+}
+
+impl Serialize for ItemType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.item_type())
+    }
+}
+
+impl<'de> Deserialize<'de> for ItemType {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl std::str::FromStr for ItemType {
+    type Err = String;
+
+    /// 単数形・複数形のどちらも受け付ける
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        ItemType::ALL.into_iter()
+            .find(|ty| ty.item_type() == s || ty.path() == s)
+            .ok_or_else(|| format!("Unknown item type: {}", s))
+    }
+}
+
+/// 多言語テキスト（{"en": "...", "ja": "..."}形式）
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct LocalizationText(pub BTreeMap<String, String>);
+
+impl LocalizationText {
+    pub fn new() -> Self {
+        Self(BTreeMap::new())
+    }
+
+    /// 単一ロケールのテキストを作成
+    pub fn single(locale: &str, text: impl Into<String>) -> Self {
+        let mut map = BTreeMap::new();
+        map.insert(locale.to_string(), text.into());
+        Self(map)
+    }
+
+    pub fn get(&self, locale: &str) -> Option<&str> {
+        self.0.get(locale).map(|s| s.as_str())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// リポジトリ内リソースへの参照
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Srl {
+    pub hash: String,
+    pub url: String,
+}
+
+/// アイテムのタグ
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tag<T = LocalizationText> {
+    pub title: T,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+}
+
+impl<T> Tag<T> {
+    pub fn map_text<U>(self, f: &mut impl FnMut(T) -> U) -> Tag<U> {
+        Tag {
+            title: f(self.title),
+            icon: self.icon,
+        }
+    }
+}
+
+/// 他アイテムへの参照（名前参照または埋め込みアイテム）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ItemRef<I> {
+    Name(String),
+    Item(Box<I>),
+}
+
+/// レベルが使用するスキン等の指定
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UseItem<I> {
+    pub use_default: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item: Option<ItemRef<I>>,
+}
+
+impl<I: Item> ItemRef<I> {
+    fn link(&self, field: impl Into<String>, item_type: ItemType) -> ItemLink<'_> {
+        let (name, embedded) = match self {
+            ItemRef::Name(name) => (name.as_str(), None),
+            ItemRef::Item(item) => (item.name(), Some(item.as_ref() as &dyn Item)),
+        };
+        ItemLink {
+            field: field.into(),
+            item_type,
+            name,
+            embedded,
+        }
+    }
+}
+
+impl<I> ItemRef<I> {
+    fn map_item<J>(self, f: impl FnOnce(I) -> J) -> ItemRef<J> {
+        match self {
+            ItemRef::Name(name) => ItemRef::Name(name),
+            ItemRef::Item(item) => ItemRef::Item(Box::new(f(*item))),
+        }
+    }
+
+    /// 名前での参照なら参照先の名前を変更（埋め込みアイテムはそのまま）
+    fn rename(&mut self, old: &str, new: &str) {
+        if let ItemRef::Name(name) = self {
+            if name == old {
+                *name = new.to_string();
+            }
+        }
+    }
+}
+
+impl<I: Item + Clone> ItemRef<I> {
+    /// 名前での参照を`items`の同名のアイテムの埋め込みに置き換え、埋め込んだアイテムを返す
+    fn embed(&mut self, items: &[I], item_type: ItemType, owner: &str) -> Result<&mut I> {
+        if let ItemRef::Name(name) = self {
+            let item = items.iter()
+                .find(|item| item.name() == name.as_str())
+                .ok_or_else(|| ScpError::InvalidFormat(format!(
+                    "{} references missing item: {}/{}",
+                    owner,
+                    item_type.path(),
+                    name
+                )))?;
+            *self = ItemRef::Item(Box::new(item.clone()));
+        }
+        match self {
+            ItemRef::Item(item) => Ok(item),
+            ItemRef::Name(_) => unreachable!(),
+        }
+    }
+}
+
+impl<I: Item + Clone> UseItem<I> {
+    fn embed(&mut self, items: &[I], item_type: ItemType, owner: &str) -> Result<()> {
+        if let Some(item) = &mut self.item {
+            item.embed(items, item_type, owner)?;
+        }
+        Ok(())
+    }
+}
+
+impl<I> UseItem<I> {
+    fn map_item<J>(self, f: impl FnOnce(I) -> J) -> UseItem<J> {
+        UseItem {
+            use_default: self.use_default,
+            item: self.item.map(|item| item.map_item(f)),
+        }
+    }
+
+    fn rename(&mut self, old: &str, new: &str) {
+        if let Some(item) = &mut self.item {
+            item.rename(old, new);
+        }
+    }
+}
+
+fn map_tags<T, U>(tags: Vec<Tag<T>>, f: &mut impl FnMut(T) -> U) -> Vec<Tag<U>> {
+    tags.into_iter().map(|tag| tag.map_text(f)).collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Skin<T = LocalizationText> {
+    pub name: String,
+    pub version: u32,
+    pub title: T,
+    pub subtitle: T,
+    pub author: T,
+    #[serde(default)]
+    pub tags: Vec<Tag<T>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<T>,
+    pub thumbnail: Srl,
+    pub data: Srl,
+    pub texture: Srl,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl<T> Skin<T> {
+    pub fn map_text<U>(self, f: &mut impl FnMut(T) -> U) -> Skin<U> {
+        Skin {
+            name: self.name,
+            version: self.version,
+            title: f(self.title),
+            subtitle: f(self.subtitle),
+            author: f(self.author),
+            tags: map_tags(self.tags, f),
+            description: self.description.map(&mut *f),
+            thumbnail: self.thumbnail,
+            data: self.data,
+            texture: self.texture,
+            extra: self.extra,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Background<T = LocalizationText> {
+    pub name: String,
+    pub version: u32,
+    pub title: T,
+    pub subtitle: T,
+    pub author: T,
+    #[serde(default)]
+    pub tags: Vec<Tag<T>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<T>,
+    pub thumbnail: Srl,
+    pub data: Srl,
+    pub image: Srl,
+    pub configuration: Srl,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl<T> Background<T> {
+    pub fn map_text<U>(self, f: &mut impl FnMut(T) -> U) -> Background<U> {
+        Background {
+            name: self.name,
+            version: self.version,
+            title: f(self.title),
+            subtitle: f(self.subtitle),
+            author: f(self.author),
+            tags: map_tags(self.tags, f),
+            description: self.description.map(&mut *f),
+            thumbnail: self.thumbnail,
+            data: self.data,
+            image: self.image,
+            configuration: self.configuration,
+            extra: self.extra,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Effect<T = LocalizationText> {
+    pub name: String,
+    pub version: u32,
+    pub title: T,
+    pub subtitle: T,
+    pub author: T,
+    #[serde(default)]
+    pub tags: Vec<Tag<T>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<T>,
+    pub thumbnail: Srl,
+    pub data: Srl,
+    pub audio: Srl,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl<T> Effect<T> {
+    pub fn map_text<U>(self, f: &mut impl FnMut(T) -> U) -> Effect<U> {
+        Effect {
+            name: self.name,
+            version: self.version,
+            title: f(self.title),
+            subtitle: f(self.subtitle),
+            author: f(self.author),
+            tags: map_tags(self.tags, f),
+            description: self.description.map(&mut *f),
+            thumbnail: self.thumbnail,
+            data: self.data,
+            audio: self.audio,
+            extra: self.extra,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Particle<T = LocalizationText> {
+    pub name: String,
+    pub version: u32,
+    pub title: T,
+    pub subtitle: T,
+    pub author: T,
+    #[serde(default)]
+    pub tags: Vec<Tag<T>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<T>,
+    pub thumbnail: Srl,
+    pub data: Srl,
+    pub texture: Srl,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl<T> Particle<T> {
+    pub fn map_text<U>(self, f: &mut impl FnMut(T) -> U) -> Particle<U> {
+        Particle {
+            name: self.name,
+            version: self.version,
+            title: f(self.title),
+            subtitle: f(self.subtitle),
+            author: f(self.author),
+            tags: map_tags(self.tags, f),
+            description: self.description.map(&mut *f),
+            thumbnail: self.thumbnail,
+            data: self.data,
+            texture: self.texture,
+            extra: self.extra,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Engine<T = LocalizationText> {
+    pub name: String,
+    pub version: u32,
+    pub title: T,
+    pub subtitle: T,
+    pub author: T,
+    #[serde(default)]
+    pub tags: Vec<Tag<T>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<T>,
+    pub skin: ItemRef<Skin<T>>,
+    pub background: ItemRef<Background<T>>,
+    pub effect: ItemRef<Effect<T>>,
+    pub particle: ItemRef<Particle<T>>,
+    pub thumbnail: Srl,
+    pub play_data: Srl,
+    pub watch_data: Srl,
+    pub preview_data: Srl,
+    pub tutorial_data: Srl,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rom: Option<Srl>,
+    pub configuration: Srl,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl<T> Engine<T> {
+    pub fn map_text<U>(self, f: &mut impl FnMut(T) -> U) -> Engine<U> {
+        Engine {
+            name: self.name,
+            version: self.version,
+            title: f(self.title),
+            subtitle: f(self.subtitle),
+            author: f(self.author),
+            tags: map_tags(self.tags, f),
+            description: self.description.map(&mut *f),
+            skin: self.skin.map_item(|item| item.map_text(f)),
+            background: self.background.map_item(|item| item.map_text(f)),
+            effect: self.effect.map_item(|item| item.map_text(f)),
+            particle: self.particle.map_item(|item| item.map_text(f)),
+            thumbnail: self.thumbnail,
+            play_data: self.play_data,
+            watch_data: self.watch_data,
+            preview_data: self.preview_data,
+            tutorial_data: self.tutorial_data,
+            rom: self.rom,
+            configuration: self.configuration,
+            extra: self.extra,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Level<T = LocalizationText> {
+    pub name: String,
+    pub version: u32,
+    pub rating: Number,
+    pub title: T,
+    pub artists: T,
+    pub author: T,
+    #[serde(default)]
+    pub tags: Vec<Tag<T>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<T>,
+    pub engine: ItemRef<Engine<T>>,
+    pub use_skin: UseItem<Skin<T>>,
+    pub use_background: UseItem<Background<T>>,
+    pub use_effect: UseItem<Effect<T>>,
+    pub use_particle: UseItem<Particle<T>>,
+    pub cover: Srl,
+    pub bgm: Srl,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview: Option<Srl>,
+    pub data: Srl,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl<T> Level<T> {
+    pub fn map_text<U>(self, f: &mut impl FnMut(T) -> U) -> Level<U> {
+        Level {
+            name: self.name,
+            version: self.version,
+            rating: self.rating,
+            title: f(self.title),
+            artists: f(self.artists),
+            author: f(self.author),
+            tags: map_tags(self.tags, f),
+            description: self.description.map(&mut *f),
+            engine: self.engine.map_item(|item| item.map_text(f)),
+            use_skin: self.use_skin.map_item(|item| item.map_text(f)),
+            use_background: self.use_background.map_item(|item| item.map_text(f)),
+            use_effect: self.use_effect.map_item(|item| item.map_text(f)),
+            use_particle: self.use_particle.map_item(|item| item.map_text(f)),
+            cover: self.cover,
+            bgm: self.bgm,
+            preview: self.preview,
+            data: self.data,
+            extra: self.extra,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Replay<T = LocalizationText> {
+    pub name: String,
+    pub version: u32,
+    pub title: T,
+    pub subtitle: T,
+    pub author: T,
+    #[serde(default)]
+    pub tags: Vec<Tag<T>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<T>,
+    pub level: ItemRef<Level<T>>,
+    pub data: Srl,
+    pub configuration: Srl,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl<T> Replay<T> {
+    pub fn map_text<U>(self, f: &mut impl FnMut(T) -> U) -> Replay<U> {
+        Replay {
+            name: self.name,
+            version: self.version,
+            title: f(self.title),
+            subtitle: f(self.subtitle),
+            author: f(self.author),
+            tags: map_tags(self.tags, f),
+            description: self.description.map(&mut *f),
+            level: self.level.map_item(|item| item.map_text(f)),
+            data: self.data,
+            configuration: self.configuration,
+            extra: self.extra,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Playlist<T = LocalizationText> {
+    pub name: String,
+    pub version: u32,
+    pub title: T,
+    pub subtitle: T,
+    pub author: T,
+    #[serde(default)]
+    pub tags: Vec<Tag<T>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<T>,
+    #[serde(default)]
+    pub levels: Vec<ItemRef<Level<T>>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<Srl>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl<T> Playlist<T> {
+    pub fn map_text<U>(self, f: &mut impl FnMut(T) -> U) -> Playlist<U> {
+        Playlist {
+            name: self.name,
+            version: self.version,
+            title: f(self.title),
+            subtitle: f(self.subtitle),
+            author: f(self.author),
+            tags: map_tags(self.tags, f),
+            description: self.description.map(&mut *f),
+            levels: self.levels.into_iter()
+                .map(|level| level.map_item(|item| item.map_text(f)))
+                .collect(),
+            thumbnail: self.thumbnail,
+            extra: self.extra,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Post<T = LocalizationText> {
+    pub name: String,
+    pub version: u32,
+    pub title: T,
+    pub time: i64,
+    pub author: T,
+    #[serde(default)]
+    pub tags: Vec<Tag<T>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<T>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<Srl>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl<T> Post<T> {
+    pub fn map_text<U>(self, f: &mut impl FnMut(T) -> U) -> Post<U> {
+        Post {
+            name: self.name,
+            version: self.version,
+            title: f(self.title),
+            time: self.time,
+            author: f(self.author),
+            tags: map_tags(self.tags, f),
+            description: self.description.map(&mut *f),
+            thumbnail: self.thumbnail,
+            extra: self.extra,
+        }
+    }
+}
+
+/// サーバー情報のボタン
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerButton {
+    #[serde(rename = "type")]
+    pub button_type: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl ServerButton {
+    pub fn new(button_type: &str) -> Self {
+        Self {
+            button_type: button_type.to_string(),
+            extra: Map::new(),
+        }
+    }
+}
+
+/// サーバー情報（`static/sonolus/info`）
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerInfo<T = LocalizationText> {
+    pub title: T,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<T>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub banner: Option<Srl>,
+    /// 省略時はアイテムのあるカテゴリのボタンを自動で作成
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buttons: Option<Vec<ServerButton>>,
+    /// 省略時は`{"options": []}`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub configuration: Option<Value>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl<T> ServerInfo<T> {
+    pub fn map_text<U>(self, f: &mut impl FnMut(T) -> U) -> ServerInfo<U> {
+        ServerInfo {
+            title: f(self.title),
+            description: self.description.map(&mut *f),
+            banner: self.banner,
+            buttons: self.buttons,
+            configuration: self.configuration,
+            extra: self.extra,
+        }
+    }
+}
+
+/// パッケージ情報（`static/sonolus/package`）
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageInfo {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub should_update: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl PackageInfo {
+    pub fn is_empty(&self) -> bool {
+        self.should_update.is_none() && self.extra.is_empty()
+    }
+}
+
+/// レベルのリザルト情報（`static/sonolus/levels/result/info`）
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LevelResultInfo {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub submits: Option<Vec<Value>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl LevelResultInfo {
+    pub fn is_empty(&self) -> bool {
+        self.submits.is_none() && self.extra.is_empty()
+    }
+}
+
+/// 静的ファイルの生成結果を上書きする内容（SCP → Pack → SCPを可逆にするため）
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaticOverrides {
+    /// アイテムの個別ページのフィールド（`actions`・`hasCommunity`・`leaderboards`・`sections`など）
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub item_pages: BTreeMap<ItemType, BTreeMap<String, Map<String, Value>>>,
+    /// カテゴリの`info`の内容
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub category_info: BTreeMap<ItemType, Value>,
+    /// 出力しないエントリのパス
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub omit: Vec<String>,
+}
+
+impl StaticOverrides {
+    pub fn item_page(&self, item_type: ItemType, name: &str) -> Option<&Map<String, Value>> {
+        self.item_pages.get(&item_type)?.get(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.item_pages.is_empty() && self.category_info.is_empty() && self.omit.is_empty()
+    }
+}
+
+/// pack形式（多言語テキスト）のアイテムに共通の操作
+pub trait Item {
+    fn name(&self) -> &str;
+
+    fn version(&self) -> u32;
+
+    fn description(&self) -> Option<&LocalizationText>;
+
+    /// 静的ファイル形式（テキストを解決済み）のJSONに変換
+    fn to_static(&self, resolve: &mut dyn FnMut(LocalizationText) -> String) -> Result<Value>;
+
+    /// db.jsonのJSONに変換
+    fn to_value(&self) -> Result<Value>;
+
+    /// 他のアイテムへの参照（レベル → エンジンなど）
+    fn links(&self) -> Vec<ItemLink<'_>>;
+}
+
+/// アイテムから他のアイテムへの参照
+pub struct ItemLink<'a> {
+    /// 参照しているフィールド（例: `engine`、`useSkin.item`、`levels[0]`）
+    pub field: String,
+    pub item_type: ItemType,
+    pub name: &'a str,
+    /// 名前ではなくアイテムを埋め込んでいる場合のアイテム
+    pub embedded: Option<&'a dyn Item>,
+}
+
+/// アイテムの参照（参照を持たないカテゴリは空）
+trait ItemLinks {
+    fn item_links(&self) -> Vec<ItemLink<'_>> {
+        Vec::new()
+    }
+}
+
+impl ItemLinks for Skin {}
+impl ItemLinks for Background {}
+impl ItemLinks for Effect {}
+impl ItemLinks for Particle {}
+impl ItemLinks for Post {}
+
+impl ItemLinks for Engine {
+    fn item_links(&self) -> Vec<ItemLink<'_>> {
+        vec![
+            self.skin.link("skin", ItemType::Skin),
+            self.background.link("background", ItemType::Background),
+            self.effect.link("effect", ItemType::Effect),
+            self.particle.link("particle", ItemType::Particle),
+        ]
+    }
+}
+
+impl ItemLinks for Level {
+    fn item_links(&self) -> Vec<ItemLink<'_>> {
+        let mut links = vec![self.engine.link("engine", ItemType::Engine)];
+        if let Some(item) = &self.use_skin.item {
+            links.push(item.link("useSkin.item", ItemType::Skin));
+        }
+        if let Some(item) = &self.use_background.item {
+            links.push(item.link("useBackground.item", ItemType::Background));
+        }
+        if let Some(item) = &self.use_effect.item {
+            links.push(item.link("useEffect.item", ItemType::Effect));
+        }
+        if let Some(item) = &self.use_particle.item {
+            links.push(item.link("useParticle.item", ItemType::Particle));
+        }
+        links
+    }
+}
+
+impl ItemLinks for Replay {
+    fn item_links(&self) -> Vec<ItemLink<'_>> {
+        vec![self.level.link("level", ItemType::Level)]
+    }
+}
+
+impl ItemLinks for Playlist {
+    fn item_links(&self) -> Vec<ItemLink<'_>> {
+        self.levels.iter()
+            .enumerate()
+            .map(|(index, level)| level.link(format!("levels[{}]", index), ItemType::Level))
+            .collect()
+    }
+}
+
+macro_rules! impl_item {
+    ($($ty:ident),*) => {
+        $(
+            impl Item for $ty {
+                fn name(&self) -> &str {
+                    &self.name
+                }
+
+                fn version(&self) -> u32 {
+                    self.version
+                }
+
+                fn description(&self) -> Option<&LocalizationText> {
+                    self.description.as_ref()
+                }
+
+                fn to_static(&self, resolve: &mut dyn FnMut(LocalizationText) -> String) -> Result<Value> {
+                    let item = self.clone().map_text(&mut |text| resolve(text));
+                    Ok(serde_json::to_value(item)?)
+                }
+
+                fn to_value(&self) -> Result<Value> {
+                    Ok(serde_json::to_value(self)?)
+                }
+
+                fn links(&self) -> Vec<ItemLink<'_>> {
+                    self.item_links()
+                }
+            }
+        )*
+    };
+}
+
+impl_item!(Skin, Background, Effect, Particle, Engine, Level, Replay, Playlist, Post);
+
+/// db.jsonの型付き表現
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PackDatabase {
+    pub info: ServerInfo,
+    pub posts: Vec<Post>,
+    pub playlists: Vec<Playlist>,
+    pub levels: Vec<Level>,
+    pub skins: Vec<Skin>,
+    pub backgrounds: Vec<Background>,
+    pub effects: Vec<Effect>,
+    pub particles: Vec<Particle>,
+    pub engines: Vec<Engine>,
+    pub replays: Vec<Replay>,
+    /// パッケージ情報
+    #[serde(default, skip_serializing_if = "PackageInfo::is_empty")]
+    pub package: PackageInfo,
+    /// レベルのリザルト情報
+    #[serde(rename = "levelResultInfo", default, skip_serializing_if = "LevelResultInfo::is_empty")]
+    pub level_result_info: LevelResultInfo,
+    /// 静的ファイル生成の設定
+    #[serde(default, skip_serializing_if = "PackConfig::is_default")]
+    pub config: PackConfig,
+    /// 静的ファイルの生成結果の上書き
+    #[serde(default, skip_serializing_if = "StaticOverrides::is_empty")]
+    pub overrides: StaticOverrides,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl PackDatabase {
+    /// db.jsonのJSONから読み込み（エラーにはカテゴリとアイテム名を含める）
+    pub fn from_value(value: Value) -> Result<Self> {
+        let mut object = match value {
+            Value::Object(object) => object,
+            _ => return Err(ScpError::InvalidFormat("db.json must be an object".to_string())),
+        };
+
+        Ok(Self {
+            info: parse_info(object.remove("info"))?,
+            posts: parse_items(ItemType::Post, object.remove("posts"))?,
+            playlists: parse_items(ItemType::Playlist, object.remove("playlists"))?,
+            levels: parse_items(ItemType::Level, object.remove("levels"))?,
+            skins: parse_items(ItemType::Skin, object.remove("skins"))?,
+            backgrounds: parse_items(ItemType::Background, object.remove("backgrounds"))?,
+            effects: parse_items(ItemType::Effect, object.remove("effects"))?,
+            particles: parse_items(ItemType::Particle, object.remove("particles"))?,
+            engines: parse_items(ItemType::Engine, object.remove("engines"))?,
+            replays: parse_items(ItemType::Replay, object.remove("replays"))?,
+            package: parse_package(object.remove("package"))?,
+            level_result_info: parse_level_result_info(object.remove("levelResultInfo"))?,
+            config: parse_config(object.remove("config"))?,
+            overrides: parse_overrides(object.remove("overrides"))?,
+            extra: object,
+        })
+    }
+
+    /// packディレクトリのdb.jsonを読み込み
+    pub fn load(pack_dir: &Path) -> Result<Self> {
+        let db_content = std::fs::read_to_string(pack_dir.join("db.json"))?;
+        Self::from_value(serde_json::from_str(&db_content)?)
+    }
+
+    /// カテゴリに対応するアイテム一覧
+    pub fn items(&self, item_type: ItemType) -> Vec<&dyn Item> {
+        fn erase<I: Item>(items: &[I]) -> Vec<&dyn Item> {
+            items.iter().map(|item| item as &dyn Item).collect()
+        }
+
+        match item_type {
+            ItemType::Skin => erase(&self.skins),
+            ItemType::Background => erase(&self.backgrounds),
+            ItemType::Effect => erase(&self.effects),
+            ItemType::Particle => erase(&self.particles),
+            ItemType::Engine => erase(&self.engines),
+            ItemType::Level => erase(&self.levels),
+            ItemType::Replay => erase(&self.replays),
+            ItemType::Playlist => erase(&self.playlists),
+            ItemType::Post => erase(&self.posts),
+        }
+    }
+
+    /// 条件を満たすアイテムだけを残す
+    pub fn retain_items(&mut self, mut keep: impl FnMut(ItemType, &str) -> bool) {
+        self.skins.retain(|item| keep(ItemType::Skin, &item.name));
+        self.backgrounds.retain(|item| keep(ItemType::Background, &item.name));
+        self.effects.retain(|item| keep(ItemType::Effect, &item.name));
+        self.particles.retain(|item| keep(ItemType::Particle, &item.name));
+        self.engines.retain(|item| keep(ItemType::Engine, &item.name));
+        self.levels.retain(|item| keep(ItemType::Level, &item.name));
+        self.replays.retain(|item| keep(ItemType::Replay, &item.name));
+        self.playlists.retain(|item| keep(ItemType::Playlist, &item.name));
+        self.posts.retain(|item| keep(ItemType::Post, &item.name));
+    }
+
+    /// 名前での参照をすべて参照先のアイテムの埋め込みに置き換えたdbを作成
+    ///
+    /// Sonolusは静的ファイルの参照にアイテムそのものを要求するため、名前のままでは読み込めない。
+    /// 参照先のアイテムがdb.jsonに無い場合はエラー。
+    pub fn with_embedded_references(&self) -> Result<PackDatabase> {
+        let mut db = self.clone();
+        
+        for engine in &mut db.engines {
+            embed_engine(engine, self)?;
+        }
+        for level in &mut db.levels {
+            embed_level(level, self, &db.engines)?;
+        }
+        for replay in &mut db.replays {
+            let owner = format!("replays/{}", replay.name);
+            let level = replay.level.embed(&db.levels, ItemType::Level, &owner)?;
+            embed_level(level, self, &db.engines)?;
+        }
+        for playlist in &mut db.playlists {
+            let owner = format!("playlists/{}", playlist.name);
+            for level in &mut playlist.levels {
+                let level = level.embed(&db.levels, ItemType::Level, &owner)?;
+                embed_level(level, self, &db.engines)?;
+            }
+        }
+        
+        Ok(db)
+    }
+    
+    /// アイテムの名前を変更し、名前での参照・おすすめ・個別ページの上書きも書き換える
+    pub fn rename_item(&mut self, item_type: ItemType, old: &str, new: &str) {
+        macro_rules! rename_in {
+            ($items:expr) => {
+                for item in $items.iter_mut().filter(|item| item.name == old) {
+                    item.name = new.to_string();
+                }
+            };
+        }
+
+        match item_type {
+            ItemType::Skin => {
+                rename_in!(self.skins);
+                self.engines.iter_mut().for_each(|engine| engine.skin.rename(old, new));
+                self.levels.iter_mut().for_each(|level| level.use_skin.rename(old, new));
+            }
+            ItemType::Background => {
+                rename_in!(self.backgrounds);
+                self.engines.iter_mut().for_each(|engine| engine.background.rename(old, new));
+                self.levels.iter_mut().for_each(|level| level.use_background.rename(old, new));
+            }
+            ItemType::Effect => {
+                rename_in!(self.effects);
+                self.engines.iter_mut().for_each(|engine| engine.effect.rename(old, new));
+                self.levels.iter_mut().for_each(|level| level.use_effect.rename(old, new));
+            }
+            ItemType::Particle => {
+                rename_in!(self.particles);
+                self.engines.iter_mut().for_each(|engine| engine.particle.rename(old, new));
+                self.levels.iter_mut().for_each(|level| level.use_particle.rename(old, new));
+            }
+            ItemType::Engine => {
+                rename_in!(self.engines);
+                self.levels.iter_mut().for_each(|level| level.engine.rename(old, new));
+            }
+            ItemType::Level => {
+                rename_in!(self.levels);
+                self.replays.iter_mut().for_each(|replay| replay.level.rename(old, new));
+                self.playlists.iter_mut()
+                    .flat_map(|playlist| playlist.levels.iter_mut())
+                    .for_each(|level| level.rename(old, new));
+            }
+            ItemType::Replay => rename_in!(self.replays),
+            ItemType::Playlist => rename_in!(self.playlists),
+            ItemType::Post => rename_in!(self.posts),
+        }
+
+        if let Some(recommendations) = self.config.recommendations.get_mut(&item_type) {
+            if let Some(names) = recommendations.remove(old) {
+                recommendations.insert(new.to_string(), names);
+            }
+            for name in recommendations.values_mut().flatten().filter(|name| *name == old) {
+                *name = new.to_string();
+            }
+        }
+        if let Some(pages) = self.overrides.item_pages.get_mut(&item_type) {
+            if let Some(page) = pages.remove(old) {
+                pages.insert(new.to_string(), page);
+            }
+        }
+    }
+
+    /// アイテムが1つ以上あるカテゴリ
+    pub fn non_empty_item_types(&self) -> Vec<ItemType> {
+        ItemType::ALL.into_iter()
+            .filter(|item_type| !self.items(*item_type).is_empty())
+            .collect()
+    }
+
+    /// 静的ファイル形式のアイテムをpack形式に変換して追加
+    pub fn push_static(
+        &mut self,
+        item_type: ItemType,
+        item: Value,
+        localize: &mut dyn FnMut(String) -> LocalizationText,
+    ) -> Result<()> {
+        let mut localize = |text| localize(text);
+
+        match item_type {
+            ItemType::Skin => self.skins.push(parse_item::<Skin<String>>(item_type, item)?.map_text(&mut localize)),
+            ItemType::Background => self.backgrounds.push(parse_item::<Background<String>>(item_type, item)?.map_text(&mut localize)),
+            ItemType::Effect => self.effects.push(parse_item::<Effect<String>>(item_type, item)?.map_text(&mut localize)),
+            ItemType::Particle => self.particles.push(parse_item::<Particle<String>>(item_type, item)?.map_text(&mut localize)),
+            ItemType::Engine => self.engines.push(parse_item::<Engine<String>>(item_type, item)?.map_text(&mut localize)),
+            ItemType::Level => self.levels.push(parse_item::<Level<String>>(item_type, item)?.map_text(&mut localize)),
+            ItemType::Replay => self.replays.push(parse_item::<Replay<String>>(item_type, item)?.map_text(&mut localize)),
+            ItemType::Playlist => self.playlists.push(parse_item::<Playlist<String>>(item_type, item)?.map_text(&mut localize)),
+            ItemType::Post => self.posts.push(parse_item::<Post<String>>(item_type, item)?.map_text(&mut localize)),
+        }
+
+        Ok(())
+    }
+}
+
+/// サーバー情報を読み込み（省略時は空のタイトル）
+fn parse_info(value: Option<Value>) -> Result<ServerInfo> {
+    match value {
+        None | Some(Value::Null) => Ok(ServerInfo::default()),
+        Some(value) => serde_json::from_value(value)
+            .map_err(|e| ScpError::InvalidFormat(format!("Invalid info: {}", e))),
+    }
+}
+
+/// パッケージ情報を読み込み（省略時は空）
+fn parse_package(value: Option<Value>) -> Result<PackageInfo> {
+    match value {
+        None | Some(Value::Null) => Ok(PackageInfo::default()),
+        Some(value) => serde_json::from_value(value)
+            .map_err(|e| ScpError::InvalidFormat(format!("Invalid package: {}", e))),
+    }
+}
+
+/// レベルのリザルト情報を読み込み（省略時は空）
+fn parse_level_result_info(value: Option<Value>) -> Result<LevelResultInfo> {
+    match value {
+        None | Some(Value::Null) => Ok(LevelResultInfo::default()),
+        Some(value) => serde_json::from_value(value)
+            .map_err(|e| ScpError::InvalidFormat(format!("Invalid levelResultInfo: {}", e))),
+    }
+}
+
+/// 設定を読み込み（省略時は既定値）
+fn parse_config(value: Option<Value>) -> Result<PackConfig> {
+    match value {
+        None | Some(Value::Null) => Ok(PackConfig::default()),
+        Some(value) => serde_json::from_value(value)
+            .map_err(|e| ScpError::InvalidFormat(format!("Invalid config: {}", e))),
+    }
+}
+
+/// 静的ファイルの上書きを読み込み（省略時は空）
+fn parse_overrides(value: Option<Value>) -> Result<StaticOverrides> {
+    match value {
+        None | Some(Value::Null) => Ok(StaticOverrides::default()),
+        Some(value) => serde_json::from_value(value)
+            .map_err(|e| ScpError::InvalidFormat(format!("Invalid overrides: {}", e))),
+    }
+}
+
+/// カテゴリ内のアイテム配列を読み込み
+fn parse_items<I: DeserializeOwned>(item_type: ItemType, value: Option<Value>) -> Result<Vec<I>> {
+    match value {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::Array(items)) => items.into_iter()
+            .map(|item| parse_item(item_type, item))
+            .collect(),
+        Some(_) => Err(ScpError::InvalidFormat(format!("{} must be an array", item_type.db_key()))),
+    }
+}
+
+/// アイテム1件を読み込み
+fn parse_item<I: DeserializeOwned>(item_type: ItemType, item: Value) -> Result<I> {
+    let name = item["name"].as_str().unwrap_or("<unnamed>").to_string();
+
+    serde_json::from_value(item).map_err(|source| ScpError::InvalidItem {
+        category: item_type.db_key().to_string(),
+        name,
+        source,
+    })
+}
+
+/// エンジンのスキン等の参照を埋め込みにする
+fn embed_engine(engine: &mut Engine, db: &PackDatabase) -> Result<()> {
+    let owner = format!("engines/{}", engine.name);
+    engine.skin.embed(&db.skins, ItemType::Skin, &owner)?;
+    engine.background.embed(&db.backgrounds, ItemType::Background, &owner)?;
+    engine.effect.embed(&db.effects, ItemType::Effect, &owner)?;
+    engine.particle.embed(&db.particles, ItemType::Particle, &owner)?;
+    Ok(())
+}
+
+/// レベルのエンジン（`engines`は参照を埋め込み済み）とスキン等の参照を埋め込みにする
+fn embed_level(level: &mut Level, db: &PackDatabase, engines: &[Engine]) -> Result<()> {
+    let owner = format!("levels/{}", level.name);
+    let engine = level.engine.embed(engines, ItemType::Engine, &owner)?;
+    embed_engine(engine, db)?;
+    level.use_skin.embed(&db.skins, ItemType::Skin, &owner)?;
+    level.use_background.embed(&db.backgrounds, ItemType::Background, &owner)?;
+    level.use_effect.embed(&db.effects, ItemType::Effect, &owner)?;
+    level.use_particle.embed(&db.particles, ItemType::Particle, &owner)?;
+    Ok(())
+}
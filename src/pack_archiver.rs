@@ -1,30 +1,149 @@
+use std::collections::HashSet;
 use std::fs::File;
-use std::io::{Read, Write};
+use std::io::{Cursor, Seek, Write};
 use std::path::Path;
-use walkdir::WalkDir;
-use zip::{ZipWriter, CompressionMethod};
+use std::sync::{Arc, OnceLock};
+use zip::{DateTime, ZipArchive, ZipWriter};
 use zip::write::SimpleFileOptions;
-use serde_json::Value;
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use serde::Serialize;
+use serde_json::{Map, Value};
 
-use crate::error::Result;
-use crate::utils::{validate_pack_dir, prepare_output_dir};
+use crate::compression::{select_compression, Compression, CompressionReport, CompressionRule, RuleMatch};
+use crate::config::PackConfig;
+use crate::error::{Result, ScpError};
+use crate::integrity::{db_references, json_references};
+use crate::locale::LocaleFallback;
+use crate::model::{Item, ItemType, LevelResultInfo, PackDatabase, PackageInfo, ServerButton};
+use crate::search::{SearchIndex, MAX_SEARCH_COMBINATIONS};
+use crate::select::{resolve_selection, ItemPattern};
+use crate::source::PackSource;
+use crate::static_tree::{EntryData, StaticTree};
+use crate::utils::{validate_pack_dir, prepare_output_dir, list_page_path, decode_entry_path, zip_datetime_from_unix, EXTRA_DIR, REPOSITORY_PREFIX};
 
+/// 並列に圧縮してからまとめて書き込むエントリの数
+///
+/// 書き込むまでチャンク内の全エントリを1エントリだけのZIPとしてメモリに置くため、
+/// 圧縮中のメモリ使用量はおよそ「チャンク内のエントリの圧縮後のサイズの合計」と
+/// 「スレッド数分の圧縮前のエントリ」になる
+const COMPRESS_CHUNK_SIZE: usize = 256;
+
+/// ZIPにコピーする前の圧縮済みのエントリ
+struct CompressedEntry {
+    rule: RuleMatch,
+    compression: Compression,
+    original_size: u64,
+    archive: ZipArchive<Cursor<Vec<u8>>>,
+}
+
+#[derive(Clone)]
 pub struct PackArchiver {
-    compression_level: i64,
+    compression: Compression,
+    compression_level: Option<i64>,
+    locale: LocaleFallback,
+    page_size: Option<usize>,
+    should_update: Option<bool>,
+    reachable_only: bool,
+    includes: Vec<ItemPattern>,
+    deterministic: bool,
+    source_date_epoch: Option<i64>,
+    compression_overrides: Vec<CompressionRule>,
+    jobs: Option<usize>,
+    /// `jobs`を指定したときのスレッドプール（最初の書き込みで作り、クローンとも共有する）
+    pool: Arc<OnceLock<std::result::Result<ThreadPool, String>>>,
 }
 
 impl PackArchiver {
     pub fn new() -> Self {
         Self {
-            compression_level: 6,
+            compression: Compression::default(),
+            compression_level: None,
+            locale: LocaleFallback::default(),
+            page_size: None,
+            should_update: None,
+            reachable_only: false,
+            includes: Vec::new(),
+            deterministic: false,
+            source_date_epoch: None,
+            compression_overrides: Vec::new(),
+            jobs: None,
+            pool: Arc::default(),
         }
     }
     
+    /// 内容から圧縮方法を決められないエントリ（JSONなど）の圧縮方法を設定
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+    
+    /// `with_compression`で指定した圧縮方法のレベルを設定（省略時はその方法の既定値）
     pub fn with_compression_level(mut self, level: i64) -> Self {
-        self.compression_level = level;
+        self.compression_level = Some(level);
+        self
+    }
+    
+    /// 静的ファイルに出力する言語の優先順位を設定
+    pub fn with_locale(mut self, locale: LocaleFallback) -> Self {
+        self.locale = locale;
+        self
+    }
+    
+    /// listファイル1ページあたりのアイテム数を設定（Noneなら1ページにまとめる）
+    pub fn with_page_size(mut self, page_size: Option<usize>) -> Self {
+        self.page_size = page_size.filter(|size| *size > 0);
+        self
+    }
+    
+    /// packageファイルの`shouldUpdate`を設定（db.jsonの値より優先）
+    pub fn with_should_update(mut self, should_update: Option<bool>) -> Self {
+        self.should_update = should_update;
+        self
+    }
+    
+    /// db.jsonのSRLから参照されているrepositoryのファイルだけを含めるかを設定
+    pub fn with_reachable_only(mut self, reachable_only: bool) -> Self {
+        self.reachable_only = reachable_only;
         self
     }
     
+    /// 出力するアイテムを指定（参照しているアイテムとrepositoryのファイルも含める。空なら全アイテム）
+    pub fn with_includes(mut self, includes: Vec<ItemPattern>) -> Self {
+        self.includes = includes;
+        self
+    }
+    
+    /// 同じ入力から常に同じバイト列のSCPを作るかを設定
+    /// （エントリをパス順に並べ、更新日時とパーミッションを固定する）
+    pub fn with_deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+    
+    /// 決定的モードでのエントリの更新日時（UNIX時刻、`SOURCE_DATE_EPOCH`。Noneなら1980-01-01 00:00:00）
+    pub fn with_source_date_epoch(mut self, source_date_epoch: Option<i64>) -> Self {
+        self.source_date_epoch = source_date_epoch;
+        self
+    }
+    
+    /// パスのパターンごとの圧縮方法を設定（先に一致したものを使い、内容による判別より優先）
+    pub fn with_compression_overrides(mut self, overrides: Vec<CompressionRule>) -> Self {
+        self.compression_overrides = overrides;
+        self
+    }
+    
+    /// 圧縮に使うスレッド数を設定（NoneならCPUの数。書き込む順序はスレッド数によらない）
+    pub fn with_jobs(mut self, jobs: Option<usize>) -> Self {
+        self.jobs = jobs.filter(|jobs| *jobs > 0);
+        self.pool = Arc::default();
+        self
+    }
+    
+    pub fn locale(&self) -> &LocaleFallback {
+        &self.locale
+    }
+    
     /// packディレクトリをscpファイルにアーカイブ（pack形式から静的ファイル形式に変換）
     pub fn archive(&self, pack_dir: &Path, output_path: &Path) -> Result<()> {
         validate_pack_dir(pack_dir)?;
@@ -33,60 +152,171 @@ impl PackArchiver {
             prepare_output_dir(parent)?;
         }
         
-        let file = File::create(output_path)?;
-        let mut zip = ZipWriter::new(file);
-        let options = SimpleFileOptions::default()
-            .compression_method(CompressionMethod::Deflated)
-            .compression_level(Some(self.compression_level));
-        
-        // db.jsonを読み込み
-        let db_path = pack_dir.join("db.json");
-        let db_content = std::fs::read_to_string(&db_path)?;
-        let db: Value = serde_json::from_str(&db_content)?;
+        // 静的ファイル形式に変換
+        let tree = self.build(pack_dir)?;
         
-        // 静的ファイル形式に変換してアーカイブに追加
-        self.add_static_files(&mut zip, &db, pack_dir, &options)?;
+        // アーカイブに追加
+        let file = File::create(output_path)?;
+        let (_, report) = self.write_archive(&tree, file)?;
         
-        zip.finish()?;
+        for (path, _) in tree.iter() {
+            println!("Added: {}", path);
+        }
+        println!("{}", report);
         println!("Successfully created SCP file: {}", output_path.display());
         
         Ok(())
     }
     
-    /// 静的ファイル形式のファイルをアーカイブに追加
+    /// packディレクトリを静的ファイル形式のエントリ一覧に変換
+    pub fn build(&self, pack_dir: &Path) -> Result<StaticTree> {
+        self.build_source(PackSource::from_pack_dir(pack_dir)?)
+    }
+    
+    /// メモリ上のpackを静的ファイル形式のエントリ一覧に変換
+    pub fn build_source(&self, source: PackSource) -> Result<StaticTree> {
+        let mut db = source.db;
+        
+        // 指定されたアイテムと、その参照先だけを残す
+        if !self.includes.is_empty() {
+            let selected = resolve_selection(&db, &self.includes)?;
+            db.retain_items(|item_type, name| selected.contains(&(item_type, name.to_string())));
+            println!("Selected {} items", selected.len());
+        }
+        
+        let mut tree = self.build_static_tree(&db)?;
+        
+        // repositoryファイルをコピー
+        self.add_repository_files(&mut tree, source.repository, &source.extra, &db)?;
+        
+        // 生成できないエントリを上書き
+        self.add_extra_files(&mut tree, source.extra);
+        
+        Ok(tree)
+    }
+    
+    /// db.jsonから静的ファイル形式のJSONファイルを作成（repositoryは含まない）
+    pub fn build_static_tree(&self, db: &PackDatabase) -> Result<StaticTree> {
+        let mut tree = StaticTree::new();
+        
+        // 名前での参照は参照先のアイテムを埋め込んで出力
+        let db = &db.with_embedded_references()?;
+        self.add_static_files(&mut tree, db)?;
+        
+        // 出力しないよう指定されたエントリを削除
+        for path in &db.overrides.omit {
+            tree.remove(path);
+        }
+        
+        Ok(tree)
+    }
+    
+    /// エントリ一覧をZIP形式で書き込み（エントリごとに内容から圧縮方法を選ぶ）
+    pub fn write_archive<W: Write + Seek>(&self, tree: &StaticTree, writer: W) -> Result<(W, CompressionReport)> {
+        let mut zip = ZipWriter::new(writer);
+        let mut options = SimpleFileOptions::default();
+        let mut report = CompressionReport::default();
+        let mut entries: Vec<_> = tree.iter().collect();
+        
+        if let Some(level) = self.compression_level {
+            self.compression.check_level(level).map_err(ScpError::InvalidFormat)?;
+        }
+        
+        // JSONのキーはserde_jsonが常に同じ順（アルファベット順）で出力するため、ZIPの属性と順序だけを固定する
+        if self.deterministic {
+            let timestamp = match self.source_date_epoch {
+                Some(epoch) => zip_datetime_from_unix(epoch)?,
+                None => DateTime::default(),
+            };
+            options = options.last_modified_time(timestamp).unix_permissions(0o644);
+            entries.sort_by_key(|(path, _)| *path);
+        }
+        
+        // 読み込みと圧縮はスレッドプールで行い、書き込みはエントリの順に行う
+        // （メモリに置く圧縮済みのエントリを抑えるため、一定数ずつ処理する）
+        let pool = self.thread_pool()?;
+        
+        for chunk in entries.chunks(COMPRESS_CHUNK_SIZE) {
+            let compress = || {
+                chunk.par_iter()
+                    .map(|(path, data)| self.compress_entry(path, data, options))
+                    .collect::<Result<Vec<_>>>()
+            };
+            let compressed = match pool {
+                Some(pool) => pool.install(compress)?,
+                None => compress()?,
+            };
+            
+            for mut entry in compressed {
+                let file = entry.archive.by_index_raw(0)?;
+                report.record(entry.rule, entry.compression, entry.original_size, file.compressed_size());
+                zip.raw_copy_file(file)?;
+            }
+        }
+        
+        report.sort(&self.compression_overrides);
+        Ok((zip.finish()?, report))
+    }
+    
+    /// `jobs`を指定していればそのスレッド数のプール（Noneならrayonのグローバルなプールを使う）
+    fn thread_pool(&self) -> Result<Option<&ThreadPool>> {
+        let Some(jobs) = self.jobs else {
+            return Ok(None);
+        };
+        
+        let pool = self.pool.get_or_init(|| {
+            ThreadPoolBuilder::new()
+                .num_threads(jobs)
+                .build()
+                .map_err(|e| e.to_string())
+        });
+        pool.as_ref()
+            .map(Some)
+            .map_err(|e| std::io::Error::other(e.clone()).into())
+    }
+    
+    /// エントリを読み込んで圧縮方法を選び、圧縮後のサイズを知るため1エントリだけのZIPに書き込む
+    fn compress_entry(&self, path: &str, data: &EntryData, options: SimpleFileOptions) -> Result<CompressedEntry> {
+        let data = data.read()?;
+        let (rule, compression) = select_compression(&self.compression_overrides, self.compression, path, &data);
+        
+        // 指定されたレベルは`with_compression`の方法にだけ使い、パターンで選ばれた他の方法は既定値
+        let level = self.compression_level.filter(|_| compression == self.compression);
+        let (method, level) = compression.with_level(level);
+        let options = options
+            .compression_method(method.method())
+            .compression_level(level);
+        
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file(path, options)?;
+        zip.write_all(&data)?;
+        
+        Ok(CompressedEntry {
+            rule,
+            compression,
+            original_size: data.len() as u64,
+            archive: ZipArchive::new(zip.finish()?)?,
+        })
+    }
+    
+    /// 静的ファイル形式のファイルを追加
     fn add_static_files(
         &self,
-        zip: &mut ZipWriter<File>,
-        db: &Value,
-        pack_dir: &Path,
-        options: &SimpleFileOptions,
+        tree: &mut StaticTree,
+        db: &PackDatabase,
     ) -> Result<()> {
         // infoファイルを追加
-        self.add_info_file(zip, &db["info"], options)?;
+        self.add_info_file(tree, db)?;
         
         // packageファイルを追加
-        self.add_package_file(zip, options)?;
+        self.add_package_file(tree, &db.package)?;
         
-        // repositoryファイルをコピー
-        self.add_repository_files(zip, pack_dir, options)?;
+        // levels/result/infoファイルを追加
+        self.add_level_result_info_file(tree, &db.level_result_info)?;
         
         // 各カテゴリを処理
-        let categories = vec![
-            ("skins", "skins"),
-            ("backgrounds", "backgrounds"),
-            ("effects", "effects"),
-            ("particles", "particles"),
-            ("engines", "engines"),
-            ("levels", "levels"),
-            ("replays", "replays"),
-            ("playlists", "playlists"),
-            ("posts", "posts"),
-        ];
-        
-        for (json_key, category) in categories {
-            if let Some(items) = db[json_key].as_array() {
-                self.add_category_files(zip, category, items, options)?;
-            }
+        for item_type in ItemType::ALL {
+            self.add_category_files(tree, item_type, &db.items(item_type), db)?;
         }
         
         Ok(())
@@ -95,219 +325,281 @@ impl PackArchiver {
     /// infoファイルを追加
     fn add_info_file(
         &self,
-        zip: &mut ZipWriter<File>,
-        info: &Value,
-        options: &SimpleFileOptions,
+        tree: &mut StaticTree,
+        db: &PackDatabase,
     ) -> Result<()> {
         let path = "static/sonolus/info";
-        let content = serde_json::to_string(info)?;
         
-        zip.start_file(path, *options)?;
-        zip.write_all(content.as_bytes())?;
-        println!("Added: {}", path);
+        // テキストを解決し、省略されたボタンと設定を補う
+        let mut info = db.info.clone().map_text(&mut |text| self.locale.resolve(&text));
+        if info.buttons.is_none() {
+            info.buttons = Some(
+                db.non_empty_item_types().iter()
+                    .map(|item_type| ServerButton::new(item_type.item_type()))
+                    .collect()
+            );
+        }
+        if info.configuration.is_none() {
+            info.configuration = Some(serde_json::json!({"options": []}));
+        }
         
-        Ok(())
+        tree.insert_json(path, &info)
     }
     
     /// packageファイルを追加
     fn add_package_file(
         &self,
-        zip: &mut ZipWriter<File>,
-        options: &SimpleFileOptions,
+        tree: &mut StaticTree,
+        package: &PackageInfo,
     ) -> Result<()> {
         let path = "static/sonolus/package";
-        let content = "{}";
         
-        zip.start_file(path, *options)?;
-        zip.write_all(content.as_bytes())?;
-        println!("Added: {}", path);
+        let mut package = package.clone();
+        if self.should_update.is_some() {
+            package.should_update = self.should_update;
+        }
         
-        Ok(())
+        tree.insert_json(path, &package)
+    }
+    
+    /// レベルのリザルト情報ファイルを追加
+    fn add_level_result_info_file(
+        &self,
+        tree: &mut StaticTree,
+        level_result_info: &LevelResultInfo,
+    ) -> Result<()> {
+        let path = "static/sonolus/levels/result/info";
+        tree.insert_json(path, level_result_info)
     }
     
     /// repositoryファイルを追加
     fn add_repository_files(
         &self,
-        zip: &mut ZipWriter<File>,
-        pack_dir: &Path,
-        options: &SimpleFileOptions,
+        tree: &mut StaticTree,
+        repository: Vec<(String, EntryData)>,
+        extra: &[(String, EntryData)],
+        db: &PackDatabase,
     ) -> Result<()> {
-        let repo_dir = pack_dir.join("repository");
-        
-        if repo_dir.exists() {
-            for entry in WalkDir::new(&repo_dir) {
-                let entry = entry.map_err(|e| std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    format!("Failed to read repository entry: {}", e)
-                ))?;
-                
-                let path = entry.path();
-                
-                if path.is_file() {
-                    let file_name = path.file_name()
-                        .and_then(|n| n.to_str())
-                        .ok_or_else(|| std::io::Error::new(
-                            std::io::ErrorKind::InvalidData,
-                            "Invalid file name"
-                        ))?;
-                    
-                    let zip_path = format!("static/sonolus/repository/{}", file_name);
-                    
-                    let mut file = File::open(path)?;
-                    let mut buffer = Vec::new();
-                    file.read_to_end(&mut buffer)?;
-                    
-                    zip.start_file(&zip_path, *options)?;
-                    zip.write_all(&buffer)?;
-                    
-                    println!("Added: {}", zip_path);
-                }
+        // アイテムを絞り込んだ場合は、残したアイテムから参照されているファイルだけを含める
+        let reachable: Option<HashSet<String>> = if self.reachable_only || !self.includes.is_empty() {
+            let mut references = db_references(&serde_json::to_value(db)?);
+            for (path, data) in extra {
+                references.extend(json_references(&data.read()?, &format!("{}/{}", EXTRA_DIR, path)));
             }
+            Some(references.into_iter().map(|reference| reference.hash).collect())
+        } else {
+            None
+        };
+        let mut skipped_count = 0;
+        let mut skipped_size = 0;
+        
+        for (file_name, data) in repository {
+            if reachable.as_ref().is_some_and(|reachable| !reachable.contains(&file_name)) {
+                skipped_count += 1;
+                skipped_size += data.size()?;
+                println!("Skipped unreferenced repository file: {}", file_name);
+                continue;
+            }
+            
+            let zip_path = format!("{}{}", REPOSITORY_PREFIX, file_name);
+            tree.insert(zip_path, data);
+        }
+        
+        if reachable.is_some() {
+            println!("Excluded {} unreferenced repository files ({} bytes saved)", skipped_count, skipped_size);
         }
         
         Ok(())
     }
     
+    /// extraディレクトリのファイルをそのまま追加（生成したファイルより優先）
+    fn add_extra_files(&self, tree: &mut StaticTree, extra: Vec<(String, EntryData)>) {
+        for (path, data) in extra {
+            tree.insert(decode_entry_path(&path), data);
+        }
+    }
+    
     /// カテゴリのファイルを追加
     fn add_category_files(
         &self,
-        zip: &mut ZipWriter<File>,
-        category: &str,
-        items: &[Value],
-        options: &SimpleFileOptions,
+        tree: &mut StaticTree,
+        item_type: ItemType,
+        items: &[&dyn Item],
+        db: &PackDatabase,
     ) -> Result<()> {
-        // listファイルを作成
-        let list_items: Vec<Value> = items.iter()
-            .map(|item| self.convert_item_to_static_format(item))
-            .collect();
+        let config = &db.config;
         
-        let list_data = serde_json::json!({
-            "pageCount": 1,
-            "items": list_items
-        });
-        
-        let list_path = format!("static/sonolus/{}/list", category);
-        let list_content = serde_json::to_string(&list_data)?;
-        
-        zip.start_file(&list_path, *options)?;
-        zip.write_all(list_content.as_bytes())?;
-        println!("Added: {}", list_path);
+        // listファイルを作成
+        let list_items = items.iter()
+            .map(|item| self.convert_item_to_static_format(*item))
+            .collect::<Result<Vec<Value>>>()?;
+        
+        self.add_list_files(tree, item_type, None, &list_items, db)?;
+        
+        // 検索結果のlistファイルを作成
+        let search = SearchIndex::new(&config.search, &list_items);
+        if search.combinations() > MAX_SEARCH_COMBINATIONS {
+            println!(
+                "Warning: {} search combinations in {} exceed {}; only results for a single tag or author are precomputed",
+                search.combinations(), item_type.path(), MAX_SEARCH_COMBINATIONS
+            );
+        }
+        for (filter, items) in search.results(&list_items) {
+            self.add_list_files(tree, item_type, Some(&filter), &items, db)?;
+        }
         
         // 各アイテムの個別ページを作成
-        for item in items {
-            if let Some(name) = item["name"].as_str() {
-                self.add_item_file(zip, category, name, item, options)?;
-            }
+        for (item, list_item) in items.iter().zip(&list_items) {
+            let sections: Vec<Value> = config.recommend_sections(item_type).iter()
+                .map(|section| {
+                    let recommendations = config.recommendations(item_type, item.name());
+                    let mut data = serde_json::json!({
+                        "title": section.title,
+                        "itemType": item_type.item_type(),
+                        "items": section.select(list_item, &list_items, recommendations)
+                    });
+                    if let Some(icon) = &section.icon {
+                        data["icon"] = Value::String(icon.clone());
+                    }
+                    data
+                })
+                .collect();
+            
+            let page_override = db.overrides.item_page(item_type, item.name());
+            self.add_item_file(tree, item_type, item.name(), *item, &sections, page_override)?;
         }
         
         // infoファイルを作成（カテゴリ用）
-        self.add_category_info_file(zip, category, options)?;
+        match db.overrides.category_info.get(&item_type) {
+            Some(info) => {
+                let info_path = format!("static/sonolus/{}/info", item_type.path());
+                tree.insert_json(info_path, info)?;
+            }
+            None => self.add_category_info_file(tree, item_type, &list_items, &search, config)?,
+        }
         
         Ok(())
     }
     
-    /// アイテムの個別ページを追加
+    /// listファイルをページに分割して追加（filterは検索条件）
+    fn add_list_files(
+        &self,
+        tree: &mut StaticTree,
+        item_type: ItemType,
+        filter: Option<&str>,
+        list_items: &[impl Serialize],
+        db: &PackDatabase,
+    ) -> Result<()> {
+        let page_size = self.page_size.or(db.config.page_size.filter(|size| *size > 0));
+        let pages: Vec<&[_]> = match page_size {
+            Some(size) if !list_items.is_empty() => list_items.chunks(size).collect(),
+            _ => vec![list_items],
+        };
+        
+        for (page, page_items) in pages.iter().enumerate() {
+            let list_data = serde_json::json!({
+                "pageCount": pages.len(),
+                "items": page_items
+            });
+            
+            let list_path = list_page_path(item_type, filter, page);
+            tree.insert_json(list_path, &list_data)?;
+        }
+        
+        Ok(())
+    }
+    
+    /// アイテムの個別ページを追加（page_overrideのフィールドで生成結果を上書き）
     fn add_item_file(
         &self,
-        zip: &mut ZipWriter<File>,
-        category: &str,
+        tree: &mut StaticTree,
+        item_type: ItemType,
         name: &str,
-        item: &Value,
-        options: &SimpleFileOptions,
+        item: &dyn Item,
+        sections: &[Value],
+        page_override: Option<&Map<String, Value>>,
     ) -> Result<()> {
-        let item_path = format!("static/sonolus/{}/{}", category, name);
+        let item_path = format!("static/sonolus/{}/{}", item_type.path(), name);
         
         // descriptionを抽出
-        let description = item.get("description")
-            .and_then(|d| d.get("en"))
-            .and_then(|v| v.as_str())
-            .unwrap_or("");
-        
-        // itemTypeを単数形に変換
-        let item_type = match category {
-            "skins" => "skin",
-            "backgrounds" => "background",
-            "effects" => "effect",
-            "particles" => "particle",
-            "engines" => "engine",
-            "levels" => "level",
-            "replays" => "replay",
-            "playlists" => "playlist",
-            "posts" => "post",
-            _ => category,
-        };
+        let description = item.description()
+            .map(|text| self.locale.resolve(text))
+            .unwrap_or_default();
         
-        let item_data = serde_json::json!({
-            "item": self.convert_item_to_static_format(item),
+        let mut item_data = serde_json::json!({
+            "item": self.convert_item_to_static_format(item)?,
             "description": description,
             "actions": [],
             "hasCommunity": false,
             "leaderboards": [],
-            "sections": [
-                {
-                    "title": "#RECOMMENDED",
-                    "icon": "star",
-                    "itemType": item_type,
-                    "items": []
-                }
-            ]
+            "sections": sections
         });
         
-        let item_content = serde_json::to_string(&item_data)?;
-        
-        zip.start_file(&item_path, *options)?;
-        zip.write_all(item_content.as_bytes())?;
-        println!("Added: {}", item_path);
+        for (key, value) in page_override.into_iter().flatten() {
+            item_data[key] = value.clone();
+        }
         
-        Ok(())
+        tree.insert_json(item_path, &item_data)
     }
     
     /// カテゴリのinfoファイルを追加
     fn add_category_info_file(
         &self,
-        zip: &mut ZipWriter<File>,
-        category: &str,
-        options: &SimpleFileOptions,
+        tree: &mut StaticTree,
+        item_type: ItemType,
+        list_items: &[Value],
+        search: &SearchIndex,
+        config: &PackConfig,
     ) -> Result<()> {
-        let info_path = format!("static/sonolus/{}/info", category);
+        let info_path = format!("static/sonolus/{}/info", item_type.path());
+        
+        // 設定に従ってセクションを作成
+        let sections: Vec<Value> = config.info_sections(item_type).iter()
+            .map(|section| {
+                let mut items = list_items.to_vec();
+                section.order.sort(&mut items);
+                if let Some(count) = section.item_count {
+                    items.truncate(count);
+                }
+                
+                let mut data = serde_json::json!({
+                    "title": section.title,
+                    "itemType": item_type.item_type(),
+                    "items": items
+                });
+                if let Some(icon) = &section.icon {
+                    data["icon"] = Value::String(icon.clone());
+                }
+                data
+            })
+            .collect();
         
         let info_data = serde_json::json!({
             "search": {
-                "options": []
-            }
+                "options": search.options()
+            },
+            "sections": sections
         });
         
-        let info_content = serde_json::to_string(&info_data)?;
-        
-        zip.start_file(&info_path, *options)?;
-        zip.write_all(info_content.as_bytes())?;
-        println!("Added: {}", info_path);
-        
-        Ok(())
+        tree.insert_json(info_path, &info_data)
     }
     
     /// pack形式のアイテムを静的ファイル形式に変換
-    fn convert_item_to_static_format(&self, item: &Value) -> Value {
-        let mut converted = item.clone();
-        
-        // {"en": "value"}形式を"value"形式に変換
-        let text_fields = vec!["title", "subtitle", "author"];
-        
-        for field in text_fields {
-            if let Some(value) = item.get(field) {
-                if let Some(en_value) = value.get("en") {
-                    if let Some(text) = en_value.as_str() {
-                        converted[field] = Value::String(text.to_string());
-                    }
-                }
-            }
-        }
+    fn convert_item_to_static_format(&self, item: &dyn Item) -> Result<Value> {
+        // {"ja": "...", "en": "..."}形式を選択した言語の"value"形式に変換
+        let mut converted = item.to_static(&mut |text| self.locale.resolve(&text))?;
         
         // descriptionは個別ページにのみ含まれるため、listからは削除
-        if converted.is_object() {
-            converted.as_object_mut().unwrap().remove("description");
+        if let Some(object) = converted.as_object_mut() {
+            object.remove("description");
         }
         
-        converted
+        Ok(converted)
     }
-}
\ No newline at end of file
+}
+
+impl Default for PackArchiver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,17 +1,46 @@
 use std::fs::{File, create_dir_all};
-use std::io::{Read, Write};
-use std::path::Path;
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
 use zip::ZipArchive;
+use zip::result::ZipError;
+use serde::de::DeserializeOwned;
 use serde_json::Value;
 
-use crate::error::Result;
-use crate::utils::{validate_scp_file, prepare_output_dir};
+use crate::compression::{is_readable, method_name, Compression};
+use crate::error::{Result, ScpError};
+use crate::locale::{merge_localized, LocaleFallback};
+use crate::model::{ItemType, LocalizationText, PackDatabase, ServerButton, ServerInfo};
+use crate::pack_archiver::PackArchiver;
+use crate::static_tree::{entries_equivalent, RawEntries};
+use crate::utils::{
+    validate_scp_file, prepare_output_dir, list_page_path, encode_entry_path, join_entry_path, repository_hash, EXTRA_DIR,
+    REPOSITORY_PREFIX,
+};
 
-pub struct PackExtractor;
+#[derive(Clone)]
+pub struct PackExtractor {
+    locale: String,
+    lossless: bool,
+}
 
 impl PackExtractor {
     pub fn new() -> Self {
-        Self
+        Self {
+            locale: "en".to_string(),
+            lossless: false,
+        }
+    }
+    
+    /// 静的ファイルのテキストをどの言語として取り込むかを設定
+    pub fn with_locale(mut self, locale: &str) -> Self {
+        self.locale = locale.to_string();
+        self
+    }
+    
+    /// 再生成できない内容をdb.jsonの`overrides`とextraディレクトリに保存するかを設定
+    pub fn with_lossless(mut self, lossless: bool) -> Self {
+        self.lossless = lossless;
+        self
     }
     
     /// SCPファイルをpackディレクトリに展開（静的ファイル形式からpack形式に変換）
@@ -27,131 +56,348 @@ impl PackExtractor {
         create_dir_all(&repo_dir)?;
         
         // db.jsonの構造を準備
-        let db = self.create_db_structure(&mut archive)?;
+        let (db, extra_entries) = self.read_pack(&mut archive)?;
+        
+        // 生成できないエントリを保存
+        self.write_extra_entries(&extra_entries, output_dir)?;
         
         // repositoryファイルをコピー
         self.extract_repository(&mut archive, &repo_dir)?;
         
         // db.jsonを書き込み
+        self.write_db(&db, output_dir)?;
+        
+        println!("Successfully converted to pack format: {}", output_dir.display());
+        Ok(())
+    }
+    
+    /// アーカイブからdb.jsonの内容を作成（可逆モードではextraディレクトリに保存するエントリも返す）
+    pub fn read_pack<R: Read + Seek>(&self, archive: &mut ZipArchive<R>) -> Result<(PackDatabase, RawEntries)> {
+        let mut db = self.create_db_structure(archive)?;
+        
+        // 再生成と異なる内容を保存
+        let extra_entries = if self.lossless {
+            self.capture_overrides(archive, &mut db)?
+        } else {
+            Vec::new()
+        };
+        
+        Ok((db, extra_entries))
+    }
+    
+    /// ロケール別のSCPファイルを1つのpackディレクトリに展開し、テキストを多言語形式にまとめる
+    pub fn extract_merged(&self, scp_files: &[(PathBuf, String)], output_dir: &Path) -> Result<()> {
+        prepare_output_dir(output_dir)?;
+        
+        // repositoryディレクトリを作成
+        let repo_dir = output_dir.join("repository");
+        create_dir_all(&repo_dir)?;
+        
+        let mut merged: Option<Value> = None;
+        
+        for (scp_file, locale) in scp_files {
+            validate_scp_file(scp_file)?;
+            println!("Reading {} as {}", scp_file.display(), locale);
+            
+            let file = File::open(scp_file)?;
+            let mut archive = ZipArchive::new(file)?;
+            
+            let extractor = self.clone().with_locale(locale);
+            let db = serde_json::to_value(extractor.create_db_structure(&mut archive)?)?;
+            extractor.extract_repository(&mut archive, &repo_dir)?;
+            
+            match merged.as_mut() {
+                Some(base) => self.merge_db(base, db),
+                None => merged = Some(db),
+            }
+        }
+        
+        // 結合結果を検証してから書き込み
+        let merged = merged.unwrap_or_else(|| serde_json::json!({}));
+        let db = PackDatabase::from_value(merged)?;
+        self.write_db(&db, output_dir)?;
+        
+        println!("Successfully merged {} SCP files to pack format: {}", scp_files.len(), output_dir.display());
+        Ok(())
+    }
+    
+    /// 別ロケールのdb.jsonを結合（アイテムは名前で対応付け）
+    fn merge_db(&self, base: &mut Value, other: Value) {
+        let Value::Object(other) = other else {
+            return;
+        };
+        
+        for (key, value) in other {
+            let is_category = ItemType::ALL.iter().any(|ty| ty.db_key() == key);
+            
+            match (base.get_mut(&key), value) {
+                (Some(Value::Array(base_items)), Value::Array(items)) if is_category => {
+                    for item in items {
+                        let existing = base_items.iter_mut()
+                            .find(|base_item| base_item["name"] == item["name"]);
+                        match existing {
+                            Some(base_item) => merge_localized(base_item, item),
+                            None => base_items.push(item),
+                        }
+                    }
+                }
+                (Some(base_value), value) => merge_localized(base_value, value),
+                (None, value) => {
+                    base[key] = value;
+                }
+            }
+        }
+    }
+    
+    /// 再生成した静的ファイルと元のSCPを比較し、差分をoverridesに保存（生成できないエントリを返す）
+    fn capture_overrides<R: Read + Seek>(&self, archive: &mut ZipArchive<R>, db: &mut PackDatabase) -> Result<RawEntries> {
+        let original = self.read_static_entries(archive)?;
+        db.config.page_size = self.infer_page_size(archive)?;
+        let generated = PackArchiver::new()
+            .with_locale(LocaleFallback::new([self.locale.clone()]))
+            .build_static_tree(db)?;
+        
+        // 元のSCPに無いエントリは出力しない
+        let original_paths: std::collections::HashSet<&str> = original.iter()
+            .map(|(path, _)| path.as_str())
+            .collect();
+        for (path, _) in generated.iter() {
+            if !original_paths.contains(path) {
+                db.overrides.omit.push(path.to_string());
+            }
+        }
+        
+        let mut extra_entries = Vec::new();
+        
+        for (path, content) in original {
+            let regenerated = match generated.get(&path) {
+                Some(data) => Some(data.read()?),
+                None => None,
+            };
+            if regenerated.as_deref().is_some_and(|regenerated| entries_equivalent(&content, regenerated)) {
+                continue;
+            }
+            
+            let original_value = serde_json::from_slice::<Value>(&content).ok();
+            let regenerated_value = regenerated.as_deref()
+                .and_then(|regenerated| serde_json::from_slice::<Value>(regenerated).ok());
+            
+            let captured = match (static_entry_kind(&path, db), original_value, regenerated_value) {
+                (StaticEntryKind::CategoryInfo(item_type), Some(value), _) => {
+                    db.overrides.category_info.insert(item_type, value);
+                    true
+                }
+                (StaticEntryKind::ItemPage(item_type, name), Some(value), Some(regenerated)) => {
+                    self.capture_item_page(db, item_type, name, value, regenerated)
+                }
+                _ => false,
+            };
+            
+            if !captured {
+                extra_entries.push((path, content));
+            }
+        }
+        
+        Ok(extra_entries)
+    }
+    
+    /// 生成できないエントリをextraディレクトリにそのまま保存
+    fn write_extra_entries(&self, extra_entries: &[(String, Vec<u8>)], output_dir: &Path) -> Result<()> {
+        let extra_dir = output_dir.join(EXTRA_DIR);
+        
+        for (path, content) in extra_entries {
+            let output_path = join_entry_path(&extra_dir, &encode_entry_path(path))?;
+            if let Some(parent) = output_path.parent() {
+                create_dir_all(parent)?;
+            }
+            std::fs::write(&output_path, content)?;
+            println!("Saved extra entry: {}", path);
+        }
+        
+        Ok(())
+    }
+    
+    /// 複数ページに分割されたlistの1ページ目のアイテム数をページサイズとみなす
+    fn infer_page_size<R: Read + Seek>(&self, archive: &mut ZipArchive<R>) -> Result<Option<usize>> {
+        for item_type in ItemType::ALL {
+            let list: Option<Value> = self.read_json_from_archive(archive, &list_page_path(item_type, None, 0))?;
+            if let Some(list) = list {
+                if list["pageCount"].as_u64().unwrap_or(1) > 1 {
+                    return Ok(list["items"].as_array().map(|items| items.len()));
+                }
+            }
+        }
+        Ok(None)
+    }
+    
+    /// 個別ページの差分をoverridesに保存（フィールドの上書きで再現できない場合はfalse）
+    fn capture_item_page(
+        &self,
+        db: &mut PackDatabase,
+        item_type: ItemType,
+        name: String,
+        original: Value,
+        mut regenerated: Value,
+    ) -> bool {
+        let (Some(original_fields), Some(regenerated_fields)) = (original.as_object(), regenerated.as_object()) else {
+            return false;
+        };
+        
+        // itemとdescriptionはdb.jsonのアイテムから作られるため上書きしない
+        let fields: serde_json::Map<String, Value> = original_fields.iter()
+            .filter(|(key, _)| *key != "item" && *key != "description")
+            .filter(|(key, value)| regenerated_fields.get(*key) != Some(*value))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        
+        for (key, value) in &fields {
+            regenerated[key] = value.clone();
+        }
+        if regenerated != original {
+            return false;
+        }
+        
+        db.overrides.item_pages.entry(item_type).or_default().insert(name, fields);
+        true
+    }
+    
+    /// repository以外のエントリをアーカイブ内の順に読み込み
+    fn read_static_entries<R: Read + Seek>(&self, archive: &mut ZipArchive<R>) -> Result<RawEntries> {
+        let mut entries = Vec::new();
+        
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            let file_name = file.name().to_string();
+            
+            if !file.is_dir() && !file_name.starts_with(REPOSITORY_PREFIX) {
+                let mut buffer = Vec::new();
+                file.read_to_end(&mut buffer)?;
+                entries.push((file_name, buffer));
+            }
+        }
+        
+        Ok(entries)
+    }
+    
+    /// db.jsonを書き込み
+    fn write_db(&self, db: &PackDatabase, output_dir: &Path) -> Result<()> {
         let db_path = output_dir.join("db.json");
-        let db_json = serde_json::to_string_pretty(&db)?;
+        let db_json = serde_json::to_string_pretty(db)?;
         let mut db_file = File::create(&db_path)?;
         db_file.write_all(db_json.as_bytes())?;
-        
-        println!("Successfully converted to pack format: {}", output_dir.display());
         Ok(())
     }
     
     /// repositoryファイルを抽出
-    fn extract_repository(&self, archive: &mut ZipArchive<File>, repo_dir: &Path) -> Result<()> {
+    fn extract_repository<R: Read + Seek>(&self, archive: &mut ZipArchive<R>, repo_dir: &Path) -> Result<()> {
         for i in 0..archive.len() {
             let mut file = archive.by_index(i)?;
             let file_name = file.name().to_string();
             
             // repositoryディレクトリ内のファイルのみを抽出
-            if file_name.starts_with("static/sonolus/repository/") && !file.is_dir() {
-                let hash = file_name.split('/').last().unwrap_or("");
-                if !hash.is_empty() {
-                    let output_path = repo_dir.join(hash);
-                    let mut buffer = Vec::new();
-                    file.read_to_end(&mut buffer)?;
-                    let mut output_file = File::create(&output_path)?;
-                    output_file.write_all(&buffer)?;
-                    println!("Copied repository file: {}", hash);
-                }
+            if let Some(hash) = repository_hash(&file_name).filter(|_| !file.is_dir()) {
+                let output_path = join_entry_path(repo_dir, hash)?;
+                let mut buffer = Vec::new();
+                file.read_to_end(&mut buffer)?;
+                let mut output_file = File::create(&output_path)?;
+                output_file.write_all(&buffer)?;
+                println!("Copied repository file: {}", hash);
             }
         }
         Ok(())
     }
     
     /// db.json構造を作成
-    fn create_db_structure(&self, archive: &mut ZipArchive<File>) -> Result<Value> {
-        let mut db = serde_json::json!({
-            "info": {"title": {}},
-            "posts": [],
-            "playlists": [],
-            "levels": [],
-            "skins": [],
-            "backgrounds": [],
-            "effects": [],
-            "particles": [],
-            "engines": [],
-            "replays": []
-        });
+    fn create_db_structure<R: Read + Seek>(&self, archive: &mut ZipArchive<R>) -> Result<PackDatabase> {
+        check_compression(archive)?;
+        let mut db = PackDatabase::default();
         
         // 各カテゴリのlistファイルを処理
-        let categories = vec![
-            ("skins", "skins"),
-            ("backgrounds", "backgrounds"),
-            ("effects", "effects"),
-            ("particles", "particles"),
-            ("engines", "engines"),
-            ("levels", "levels"),
-            ("replays", "replays"),
-            ("playlists", "playlists"),
-            ("posts", "posts"),
-        ];
-        
-        for (category, json_key) in categories {
-            if let Ok(items) = self.read_list_from_archive(archive, category) {
-                db[json_key] = items;
-            }
+        for item_type in ItemType::ALL {
+            self.read_list_from_archive(archive, item_type, &mut db)?;
         }
         
         // infoファイルを読み込み
-        if let Ok(info) = self.read_info_from_archive(archive) {
-            db["info"] = info;
+        if let Some(info) = self.read_info_from_archive(archive)? {
+            db.info = self.convert_info_to_pack_format(info, &db);
+        }
+        
+        // packageファイルを読み込み
+        if let Some(package) = self.read_json_from_archive(archive, "static/sonolus/package")? {
+            db.package = package;
+        }
+        
+        // levels/result/infoファイルを読み込み
+        if let Some(level_result_info) = self.read_json_from_archive(archive, "static/sonolus/levels/result/info")? {
+            db.level_result_info = level_result_info;
         }
         
         Ok(db)
     }
     
-    /// アーカイブからlistファイルを読み込み、pack形式に変換
-    fn read_list_from_archive(&self, archive: &mut ZipArchive<File>, category: &str) -> Result<Value> {
+    /// アーカイブからlistファイルを読み込み、pack形式に変換してdbに追加
+    fn read_list_from_archive<R: Read + Seek>(
+        &self,
+        archive: &mut ZipArchive<R>,
+        item_type: ItemType,
+        db: &mut PackDatabase,
+    ) -> Result<()> {
         // まず個別アイテムページからdescriptionを収集
-        let descriptions = self.collect_item_descriptions(archive, category)?;
+        let descriptions = self.collect_item_descriptions(archive, item_type)?;
         
-        let list_path = format!("static/sonolus/{}/list", category);
+        // 全ページを順に読み込んで結合
+        let mut page = 0;
+        let mut page_count = 1;
         
-        for i in 0..archive.len() {
-            let mut file = archive.by_index(i)?;
-            let file_name = file.name();
+        while page < page_count {
+            let list_path = list_page_path(item_type, None, page);
             
-            if file_name == list_path {
-                let mut buffer = String::new();
-                file.read_to_string(&mut buffer)?;
-                
-                let list_data: Value = serde_json::from_str(&buffer)?;
-                
-                if let Some(items) = list_data["items"].as_array() {
-                    let converted_items: Vec<Value> = items.iter()
-                        .map(|item| {
-                            let mut converted = self.convert_item_to_pack_format(item);
-                            // descriptionを追加
-                            if let Some(name) = item["name"].as_str() {
-                                if let Some(description) = descriptions.get(name) {
-                                    if !description.is_empty() {
-                                        converted["description"] = serde_json::json!({"en": description});
-                                    }
-                                }
+            let mut file = match archive.by_name(&list_path) {
+                Ok(file) => file,
+                Err(ZipError::FileNotFound) if page == 0 => return Ok(()),
+                Err(ZipError::FileNotFound) => {
+                    return Err(ScpError::InvalidFormat(format!("Missing list page: {}", list_path)));
+                }
+                Err(e) => return Err(e.into()),
+            };
+            
+            let mut buffer = String::new();
+            file.read_to_string(&mut buffer)?;
+            drop(file);
+            
+            let list_data: Value = serde_json::from_str(&buffer)?;
+            
+            if page == 0 {
+                page_count = list_data["pageCount"].as_u64().unwrap_or(1) as usize;
+            }
+            
+            if let Some(items) = list_data["items"].as_array() {
+                for item in items {
+                    let mut item = item.clone();
+                    // descriptionを追加
+                    if let Some(name) = item["name"].as_str() {
+                        if let Some(description) = descriptions.get(name) {
+                            if !description.is_empty() {
+                                item["description"] = Value::String(description.clone());
                             }
-                            converted
-                        })
-                        .collect();
-                    return Ok(Value::Array(converted_items));
+                        }
+                    }
+                    db.push_static(item_type, item, &mut |text| self.convert_text_to_pack_format(text))?;
                 }
             }
+            
+            page += 1;
         }
         
-        Ok(Value::Array(vec![]))
+        Ok(())
     }
     
     /// カテゴリ内の全アイテムのdescriptionを収集
-    fn collect_item_descriptions(&self, archive: &mut ZipArchive<File>, category: &str) -> Result<std::collections::HashMap<String, String>> {
+    fn collect_item_descriptions<R: Read + Seek>(&self, archive: &mut ZipArchive<R>, item_type: ItemType) -> Result<std::collections::HashMap<String, String>> {
         use std::collections::HashMap;
         
         let mut descriptions = HashMap::new();
-        let prefix = format!("static/sonolus/{}/", category);
+        let prefix = format!("static/sonolus/{}/", item_type.path());
         
         for i in 0..archive.len() {
             let mut file = archive.by_index(i)?;
@@ -161,7 +407,9 @@ impl PackExtractor {
             if file_name.starts_with(&prefix) && !file.is_dir() {
                 let item_name = file_name.strip_prefix(&prefix).unwrap_or("");
                 
-                if item_name != "list" && item_name != "info" && !item_name.is_empty() {
+                // "result/info"などのサブディレクトリのファイルも除く
+                if item_name != "list" && !item_name.starts_with("list?") && item_name != "info"
+                    && !item_name.contains('/') && !item_name.is_empty() {
                     let mut buffer = String::new();
                     if file.read_to_string(&mut buffer).is_ok() {
                         if let Ok(item_data) = serde_json::from_str::<Value>(&buffer) {
@@ -178,38 +426,62 @@ impl PackExtractor {
     }
     
     /// アーカイブからinfoファイルを読み込み
-    fn read_info_from_archive(&self, archive: &mut ZipArchive<File>) -> Result<Value> {
+    fn read_info_from_archive<R: Read + Seek>(&self, archive: &mut ZipArchive<R>) -> Result<Option<ServerInfo<String>>> {
         for i in 0..archive.len() {
             let mut file = archive.by_index(i)?;
             if file.name() == "static/sonolus/info" {
                 let mut buffer = String::new();
                 file.read_to_string(&mut buffer)?;
-                let info_data: Value = serde_json::from_str(&buffer)?;
-                return Ok(info_data);
+                let info_data = serde_json::from_str(&buffer)
+                    .map_err(|e| ScpError::InvalidFormat(format!("Invalid info: {}", e)))?;
+                return Ok(Some(info_data));
             }
         }
-        Ok(serde_json::json!({"title": {}}))
+        Ok(None)
     }
     
-    /// 静的ファイル形式のアイテムをpack形式に変換
-    fn convert_item_to_pack_format(&self, item: &Value) -> Value {
-        let mut converted = item.clone();
+    /// アーカイブからJSONファイルを読み込み（存在しない場合はNone）
+    fn read_json_from_archive<T: DeserializeOwned, R: Read + Seek>(&self, archive: &mut ZipArchive<R>, path: &str) -> Result<Option<T>> {
+        let mut file = match archive.by_name(path) {
+            Ok(file) => file,
+            Err(ZipError::FileNotFound) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
         
-        // 文字列フィールドを {"en": "value"} 形式に変換
-        let text_fields = vec!["title", "subtitle", "author", "description"];
-        
-        for field in text_fields {
-            if let Some(value) = item.get(field) {
-                if value.is_string() {
-                    let text = value.as_str().unwrap_or("");
-                    converted[field] = serde_json::json!({
-                        "en": text
-                    });
-                }
+        let mut buffer = String::new();
+        file.read_to_string(&mut buffer)?;
+        let data = serde_json::from_str(&buffer)
+            .map_err(|e| ScpError::InvalidFormat(format!("Invalid {}: {}", path, e)))?;
+        Ok(Some(data))
+    }
+    
+    /// 静的ファイル形式のinfoをpack形式に変換
+    /// （アーカイブ時に自動で作成されるボタンと設定は省略する）
+    fn convert_info_to_pack_format(&self, info: ServerInfo<String>, db: &PackDatabase) -> ServerInfo {
+        let mut info = info.map_text(&mut |text| {
+            if text.is_empty() {
+                LocalizationText::new()
+            } else {
+                self.convert_text_to_pack_format(text)
             }
+        });
+        
+        let default_buttons: Vec<ServerButton> = db.non_empty_item_types().iter()
+            .map(|item_type| ServerButton::new(item_type.item_type()))
+            .collect();
+        if info.buttons.as_ref() == Some(&default_buttons) {
+            info.buttons = None;
+        }
+        if info.configuration == Some(serde_json::json!({"options": []})) {
+            info.configuration = None;
         }
         
-        converted
+        info
+    }
+    
+    /// 静的ファイル形式の文字列を {"<locale>": "value"} 形式に変換
+    fn convert_text_to_pack_format(&self, text: String) -> LocalizationText {
+        LocalizationText::single(&self.locale, text)
     }
     
     /// SCPファイルの内容を一覧表示
@@ -220,12 +492,14 @@ impl PackExtractor {
         let mut archive = ZipArchive::new(file)?;
         let mut contents = Vec::new();
         
+        // 展開しないので、このビルドで読めない圧縮方法のエントリも表示できる
         for i in 0..archive.len() {
-            let file = archive.by_index(i)?;
+            let file = archive.by_index_raw(i)?;
             contents.push(format!(
-                "{} ({} bytes)", 
+                "{} ({} bytes, {})", 
                 file.name(), 
-                file.size()
+                file.size(),
+                method_name(file.compression())
             ));
         }
         
@@ -245,4 +519,58 @@ impl PackExtractor {
         
         Ok(buffer)
     }
-}
\ No newline at end of file
+}
+
+/// 静的ファイルのエントリの種類（overridesに保存できるもの）
+enum StaticEntryKind {
+    /// `<category>/info`
+    CategoryInfo(ItemType),
+    /// db.jsonにあるアイテムの個別ページ
+    ItemPage(ItemType, String),
+    Other,
+}
+
+/// このビルドで読めない圧縮方法のエントリがあれば、エントリ名と必要なcargo featureを示すエラーにする
+fn check_compression<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<()> {
+    for i in 0..archive.len() {
+        let file = archive.by_index_raw(i)?;
+        if !is_readable(file.compression()) {
+            let name = method_name(file.compression());
+            let hint = if Compression::NAMES.contains(&name.as_str()) {
+                format!("enable the `{}` feature", name)
+            } else {
+                "not supported".to_string()
+            };
+            return Err(ScpError::InvalidFormat(format!(
+                "{} is compressed with {} ({})",
+                file.name(),
+                name,
+                hint
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn static_entry_kind(path: &str, db: &PackDatabase) -> StaticEntryKind {
+    let Some((category, rest)) = path.strip_prefix("static/sonolus/").and_then(|rest| rest.split_once('/')) else {
+        return StaticEntryKind::Other;
+    };
+    let Some(item_type) = ItemType::ALL.into_iter().find(|ty| ty.path() == category) else {
+        return StaticEntryKind::Other;
+    };
+    
+    if rest == "info" {
+        StaticEntryKind::CategoryInfo(item_type)
+    } else if db.items(item_type).iter().any(|item| item.name() == rest) {
+        StaticEntryKind::ItemPage(item_type, rest.to_string())
+    } else {
+        StaticEntryKind::Other
+    }
+}
+
+impl Default for PackExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,374 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::compression::Compression;
+use crate::error::{Result, ScpError};
+use crate::integrity::sha1_reader;
+use crate::utils::{prepare_output_dir, validate_scp_file};
+
+/// パッチのマニフェストのエントリ名
+const MANIFEST_PATH: &str = "patch.json";
+/// 元のSCPから取れないバイト列をつなげたエントリ
+const DATA_PATH: &str = "data";
+const PATCH_FORMAT_VERSION: u32 = 1;
+
+/// ファイルのチェックサム
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checksum {
+    pub sha1: String,
+    pub size: u64,
+}
+
+impl Checksum {
+    pub fn of_file(path: &Path) -> Result<Self> {
+        Ok(Self {
+            sha1: sha1_reader(&mut File::open(path)?)?,
+            size: path.metadata()?.len(),
+        })
+    }
+}
+
+/// 新しいSCPのバイト列の一部
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "from", rename_all = "camelCase")]
+pub enum Segment {
+    /// 元のSCPの`offset`から`length`バイト
+    Base { offset: u64, length: u64 },
+    /// パッチの`data`エントリの続きの`length`バイト
+    Patch { length: u64 },
+}
+
+/// パッチの内容
+///
+/// 新しいSCPは`segments`を先頭から順につなげると復元できる。
+/// 内容が変わらないエントリの圧縮済みデータは元のSCPから、それ以外（ヘッダ・セントラルディレクトリ・追加や変更したエントリ）はパッチから取る
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatchManifest {
+    pub version: u32,
+    pub base: Checksum,
+    pub target: Checksum,
+    /// 追加したエントリ
+    pub added: Vec<String>,
+    /// 内容が変わったエントリ
+    pub modified: Vec<String>,
+    /// 元のSCPにあって新しいSCPに無いエントリ
+    pub deleted: Vec<String>,
+    pub segments: Vec<Segment>,
+}
+
+/// パッチに含めたエントリ
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchedEntry {
+    pub path: String,
+    /// 圧縮後のサイズ
+    pub compressed_size: u64,
+}
+
+/// `make-patch`の結果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchReport {
+    pub patch: String,
+    pub base: Checksum,
+    pub target: Checksum,
+    pub added: Vec<PatchedEntry>,
+    pub modified: Vec<PatchedEntry>,
+    pub deleted: Vec<String>,
+    /// 元のSCPからコピーするエントリ数
+    pub unchanged: usize,
+    pub patch_size: u64,
+}
+
+impl fmt::Display for PatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Patch: {}", self.patch)?;
+        writeln!(f, "Base: {} ({} bytes)", self.base.sha1, self.base.size)?;
+        writeln!(f, "Target: {} ({} bytes)", self.target.sha1, self.target.size)?;
+
+        for entry in &self.added {
+            writeln!(f, "  + {} ({} bytes)", entry.path, entry.compressed_size)?;
+        }
+        for entry in &self.modified {
+            writeln!(f, "  ~ {} ({} bytes)", entry.path, entry.compressed_size)?;
+        }
+        for path in &self.deleted {
+            writeln!(f, "  - {}", path)?;
+        }
+
+        write!(
+            f,
+            "{} added, {} modified, {} deleted, {} unchanged; patch is {} bytes ({:.1}% of target)",
+            self.added.len(),
+            self.modified.len(),
+            self.deleted.len(),
+            self.unchanged,
+            self.patch_size,
+            self.patch_size as f64 * 100.0 / self.target.size.max(1) as f64
+        )
+    }
+}
+
+/// 元のSCPと新しいSCPの差分パッチを作成
+///
+/// ZIPのバイト列を元のSCPのデータとパッチのデータに分けるため、どのツールで作ったSCPでもそのまま復元できる
+pub fn make_patch(base: &Path, target: &Path, output: &Path) -> Result<PatchReport> {
+    validate_scp_file(base)?;
+    validate_scp_file(target)?;
+
+    let mut base_archive = ZipArchive::new(File::open(base)?)?;
+    let mut target_archive = ZipArchive::new(File::open(target)?)?;
+
+    let base_entries: HashMap<String, usize> = (0..base_archive.len())
+        .filter_map(|i| base_archive.name_for_index(i).map(|name| (name.to_string(), i)))
+        .collect();
+
+    // 新しいSCPのエントリをファイル内の位置の順に調べる
+    let mut entries = Vec::new();
+    for i in 0..target_archive.len() {
+        let file = target_archive.by_index_raw(i)?;
+        entries.push((file.data_start(), file.compressed_size(), file.name().to_string(), i));
+    }
+    entries.sort();
+
+    let mut segments = Vec::new();
+    let mut added = Vec::new();
+    let mut modified = Vec::new();
+    let mut unchanged = 0;
+    let mut position = 0;
+
+    for (data_start, compressed_size, path, index) in entries {
+        let base_offset = match base_entries.get(&path) {
+            Some(&base_index) => same_raw_data(&mut base_archive, base_index, &mut target_archive, index)?,
+            None => None,
+        };
+
+        // ローカルヘッダなど、前のエントリのデータからこのエントリのデータまではパッチから取る
+        push_segment(&mut segments, Segment::Patch { length: data_start - position });
+        match base_offset {
+            Some(offset) => {
+                push_segment(&mut segments, Segment::Base { offset, length: compressed_size });
+                unchanged += 1;
+            }
+            None => {
+                push_segment(&mut segments, Segment::Patch { length: compressed_size });
+                let entry = PatchedEntry { path: path.clone(), compressed_size };
+                if base_entries.contains_key(&path) {
+                    modified.push(entry);
+                } else {
+                    added.push(entry);
+                }
+            }
+        }
+        position = data_start + compressed_size;
+    }
+    push_segment(&mut segments, Segment::Patch { length: target.metadata()?.len() - position });
+
+    let target_names: HashSet<&str> = target_archive.file_names().collect();
+    let mut deleted: Vec<String> = base_archive.file_names()
+        .filter(|name| !target_names.contains(name))
+        .map(str::to_string)
+        .collect();
+    deleted.sort();
+
+    let manifest = PatchManifest {
+        version: PATCH_FORMAT_VERSION,
+        base: Checksum::of_file(base)?,
+        target: Checksum::of_file(target)?,
+        added: added.iter().map(|entry| entry.path.clone()).collect(),
+        modified: modified.iter().map(|entry| entry.path.clone()).collect(),
+        deleted: deleted.clone(),
+        segments,
+    };
+
+    if let Some(parent) = output.parent() {
+        prepare_output_dir(parent)?;
+    }
+    write_patch(&manifest, &mut File::open(target)?, File::create(output)?)?;
+
+    Ok(PatchReport {
+        patch: output.display().to_string(),
+        base: manifest.base,
+        target: manifest.target,
+        added,
+        modified,
+        deleted,
+        unchanged,
+        patch_size: output.metadata()?.len(),
+    })
+}
+
+/// 元のSCPにパッチを適用して新しいSCPを作成
+///
+/// 元のSCPのチェックサムが異なれば適用せず、復元したSCPのチェックサムが異なれば出力を削除する
+pub fn apply_patch(base: &Path, patch: &Path, output: &Path) -> Result<PatchManifest> {
+    validate_scp_file(base)?;
+
+    let mut patch_archive = ZipArchive::new(File::open(patch)?)?;
+    let manifest = read_manifest(&mut patch_archive)?;
+
+    let base_checksum = Checksum::of_file(base)?;
+    if base_checksum != manifest.base {
+        return Err(ScpError::InvalidFormat(format!(
+            "Patch does not apply to {}: expected base SHA-1 {} ({} bytes), found {} ({} bytes)",
+            base.display(), manifest.base.sha1, manifest.base.size, base_checksum.sha1, base_checksum.size
+        )));
+    }
+
+    if let Some(parent) = output.parent() {
+        prepare_output_dir(parent)?;
+    }
+
+    let data = patch_archive.by_name(DATA_PATH)
+        .map_err(|_| ScpError::InvalidFormat(format!("Not a patch file: {} is missing", DATA_PATH)))?;
+    let written = write_target(&manifest, File::open(base)?, data, File::create(output)?);
+
+    match written {
+        Ok(checksum) if checksum == manifest.target => Ok(manifest),
+        Ok(checksum) => {
+            let _ = std::fs::remove_file(output);
+            Err(ScpError::InvalidFormat(format!(
+                "Patched SCP does not match: expected SHA-1 {}, got {}", manifest.target.sha1, checksum.sha1
+            )))
+        }
+        Err(error) => {
+            let _ = std::fs::remove_file(output);
+            Err(error)
+        }
+    }
+}
+
+/// パッチのマニフェストを読み込む
+pub fn read_manifest<R: Read + Seek>(patch: &mut ZipArchive<R>) -> Result<PatchManifest> {
+    let mut content = Vec::new();
+    patch.by_name(MANIFEST_PATH)
+        .map_err(|_| ScpError::InvalidFormat(format!("Not a patch file: {} is missing", MANIFEST_PATH)))?
+        .read_to_end(&mut content)?;
+
+    let manifest: PatchManifest = serde_json::from_slice(&content)?;
+    if manifest.version != PATCH_FORMAT_VERSION {
+        return Err(ScpError::InvalidFormat(format!("Unsupported patch version: {}", manifest.version)));
+    }
+    Ok(manifest)
+}
+
+/// マニフェストと、新しいSCPのうちパッチから取る範囲を書き込み
+fn write_patch<R: Read + Seek, W: Write + Seek>(manifest: &PatchManifest, target: &mut R, writer: W) -> Result<()> {
+    let mut zip = ZipWriter::new(writer);
+    let options = SimpleFileOptions::default()
+        .compression_method(Compression::default().method())
+        .large_file(manifest.target.size >= u32::MAX as u64);
+
+    zip.start_file(MANIFEST_PATH, options)?;
+    zip.write_all(&serde_json::to_vec_pretty(manifest)?)?;
+
+    zip.start_file(DATA_PATH, options)?;
+    let mut position = 0;
+    for segment in &manifest.segments {
+        match *segment {
+            Segment::Base { length, .. } => position += length,
+            Segment::Patch { length } => {
+                target.seek(SeekFrom::Start(position))?;
+                copy_exact(target, &mut zip, length)?;
+                position += length;
+            }
+        }
+    }
+
+    zip.finish()?;
+    Ok(())
+}
+
+/// セグメントを順に書き込みながらチェックサムを計算
+fn write_target(manifest: &PatchManifest, base: File, mut data: impl Read, output: File) -> Result<Checksum> {
+    let mut base = BufReader::new(base);
+    let mut writer = HashingWriter { inner: BufWriter::new(output), hasher: Sha1::new(), size: 0 };
+
+    for segment in &manifest.segments {
+        match *segment {
+            Segment::Base { offset, length } => {
+                base.seek(SeekFrom::Start(offset))?;
+                copy_exact(&mut base, &mut writer, length)?;
+            }
+            Segment::Patch { length } => copy_exact(&mut data, &mut writer, length)?,
+        }
+    }
+    writer.inner.flush()?;
+
+    Ok(Checksum {
+        sha1: format!("{:x}", writer.hasher.finalize()),
+        size: writer.size,
+    })
+}
+
+/// ちょうど`length`バイトをコピー（足りなければエラー）
+fn copy_exact(reader: &mut impl Read, writer: &mut impl Write, length: u64) -> Result<()> {
+    let copied = std::io::copy(&mut reader.take(length), writer)?;
+    if copied != length {
+        return Err(ScpError::InvalidFormat(format!("Unexpected end of data ({} of {} bytes)", copied, length)));
+    }
+    Ok(())
+}
+
+/// 書き込んだ内容のSHA-1とサイズを数える
+struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha1,
+    size: u64,
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// 直前のセグメントと続いていればつなげる（長さ0は追加しない）
+fn push_segment(segments: &mut Vec<Segment>, segment: Segment) {
+    match (segments.last_mut(), segment) {
+        (_, Segment::Base { length: 0, .. } | Segment::Patch { length: 0 }) => {}
+        (Some(Segment::Base { offset, length }), Segment::Base { offset: next, length: next_length })
+            if *offset + *length == next => *length += next_length,
+        (Some(Segment::Patch { length }), Segment::Patch { length: next_length }) => *length += next_length,
+        _ => segments.push(segment),
+    }
+}
+
+/// 圧縮済みのデータが同じなら、元のSCPでのデータの位置を返す
+fn same_raw_data<A: Read + Seek, B: Read + Seek>(
+    base: &mut ZipArchive<A>,
+    base_index: usize,
+    target: &mut ZipArchive<B>,
+    target_index: usize,
+) -> Result<Option<u64>> {
+    let mut base_file = base.by_index_raw(base_index)?;
+    let mut target_file = target.by_index_raw(target_index)?;
+
+    if base_file.crc32() != target_file.crc32()
+        || base_file.compressed_size() != target_file.compressed_size()
+        || base_file.compression() != target_file.compression()
+    {
+        return Ok(None);
+    }
+
+    let mut base_data = Vec::new();
+    let mut target_data = Vec::new();
+    base_file.read_to_end(&mut base_data)?;
+    target_file.read_to_end(&mut target_data)?;
+
+    Ok((base_data == target_data).then(|| base_file.data_start()))
+}
@@ -0,0 +1,167 @@
+use serde_json::Value;
+
+use crate::config::SearchConfig;
+
+/// 事前計算する検索条件の組み合わせ数の上限
+///
+/// 超える場合はタグ1つ・作者1人だけで絞り込んだ結果にする
+pub const MAX_SEARCH_COMBINATIONS: usize = 1000;
+
+/// カテゴリの検索オプションと事前計算した検索結果
+///
+/// 静的ファイルではサーバー側で絞り込めないため、キーワードが空でタグを1つまで選んだ検索条件ごとに、
+/// クライアントが送るクエリ（`keywords=&tags=<0/1の列>&author=<index>`）と同じパスに結果のlistを作っておく
+pub struct SearchIndex {
+    keywords: bool,
+    tags: Vec<Value>,
+    authors: Vec<Value>,
+}
+
+/// 事前計算する検索条件（Noneは絞り込まない）
+#[derive(Debug, Clone, Copy)]
+struct Selection {
+    tag: Option<usize>,
+    author: Option<usize>,
+}
+
+impl SearchIndex {
+    /// 静的ファイル形式のアイテム一覧からタグと作者を集める
+    pub fn new(config: &SearchConfig, items: &[Value]) -> Self {
+        let mut tags = Vec::new();
+        let mut authors = Vec::new();
+
+        for item in items {
+            if config.tags {
+                for tag in item["tags"].as_array().into_iter().flatten() {
+                    push_unique(&mut tags, &tag["title"]);
+                }
+            }
+            if config.authors {
+                push_unique(&mut authors, &item["author"]);
+            }
+        }
+
+        // 作者が1人だけなら絞り込んでも結果が変わらない
+        if authors.len() < 2 {
+            authors.clear();
+        }
+
+        Self {
+            keywords: config.keywords,
+            tags,
+            authors,
+        }
+    }
+
+    /// `<category>/info`の`search.options`
+    pub fn options(&self) -> Vec<Value> {
+        let mut options = Vec::new();
+
+        if self.keywords {
+            options.push(serde_json::json!({
+                "query": "keywords",
+                "name": "#KEYWORDS",
+                "type": "text",
+                "placeholder": "#KEYWORDS",
+                "def": ""
+            }));
+        }
+
+        if !self.tags.is_empty() {
+            options.push(serde_json::json!({
+                "query": "tags",
+                "name": "#TAGS",
+                "type": "multi",
+                "def": vec![false; self.tags.len()],
+                "values": self.tags
+            }));
+        }
+
+        if !self.authors.is_empty() {
+            let mut values = vec![Value::String("#ANY".to_string())];
+            values.extend(self.authors.iter().cloned());
+
+            options.push(serde_json::json!({
+                "query": "author",
+                "name": "#AUTHOR",
+                "type": "select",
+                "def": 0,
+                "values": values
+            }));
+        }
+
+        options
+    }
+
+    /// タグ（未選択を含む）と作者（`#ANY`を含む）の組み合わせの数
+    pub fn combinations(&self) -> usize {
+        if !self.keywords && self.tags.is_empty() && self.authors.is_empty() {
+            return 0;
+        }
+        (self.tags.len() + 1) * (self.authors.len() + 1)
+    }
+
+    /// 事前計算する検索条件のクエリと、その結果のアイテム
+    ///
+    /// 組み合わせが`MAX_SEARCH_COMBINATIONS`を超える場合は、タグ・作者の一方だけで絞り込んだ結果にする
+    pub fn results<'a>(&self, items: &'a [Value]) -> Vec<(String, Vec<&'a Value>)> {
+        let combinations = self.combinations();
+        if combinations == 0 {
+            return Vec::new();
+        }
+
+        let tags = std::iter::once(None).chain((0..self.tags.len()).map(Some));
+        let authors = || std::iter::once(None).chain((0..self.authors.len()).map(Some));
+        let selections: Vec<Selection> = if combinations <= MAX_SEARCH_COMBINATIONS {
+            tags.flat_map(|tag| authors().map(move |author| Selection { tag, author })).collect()
+        } else {
+            tags.map(|tag| Selection { tag, author: None })
+                .chain(authors().skip(1).map(|author| Selection { tag: None, author }))
+                .collect()
+        };
+
+        selections.into_iter()
+            .map(|selection| {
+                let matched = items.iter().filter(|item| self.matches(item, selection)).collect();
+                (self.query(selection), matched)
+            })
+            .collect()
+    }
+
+    /// クライアントが送るクエリ（オプションの順に全オプションを並べる）
+    fn query(&self, selection: Selection) -> String {
+        let mut query = Vec::new();
+
+        if self.keywords {
+            query.push("keywords=".to_string());
+        }
+        if !self.tags.is_empty() {
+            let flags: String = (0..self.tags.len())
+                .map(|index| if selection.tag == Some(index) { '1' } else { '0' })
+                .collect();
+            query.push(format!("tags={}", flags));
+        }
+        if !self.authors.is_empty() {
+            // 0番目は"#ANY"のため作者は1始まり
+            query.push(format!("author={}", selection.author.map_or(0, |index| index + 1)));
+        }
+
+        query.join("&")
+    }
+
+    fn matches(&self, item: &Value, selection: Selection) -> bool {
+        let tag_matches = selection.tag.is_none_or(|index| {
+            item["tags"].as_array()
+                .is_some_and(|tags| tags.iter().any(|tag| tag["title"] == self.tags[index]))
+        });
+        let author_matches = selection.author.is_none_or(|index| item["author"] == self.authors[index]);
+
+        tag_matches && author_matches
+    }
+}
+
+fn push_unique(values: &mut Vec<Value>, value: &Value) {
+    if !value.is_null() && !values.contains(value) {
+        values.push(value.clone());
+    }
+}
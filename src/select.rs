@@ -0,0 +1,99 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{Result, ScpError};
+use crate::model::{Item, ItemType, PackDatabase};
+use crate::utils::glob_match;
+
+/// 出力するアイテムのパターン（例: `levels/my-level`、`levels/chapter1-*`）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemPattern {
+    pub item_type: ItemType,
+    /// アイテム名（`*`・`?`を使える）
+    pub name: String,
+}
+
+impl ItemPattern {
+    pub fn matches(&self, item_type: ItemType, name: &str) -> bool {
+        self.item_type == item_type && glob_match(&self.name, name)
+    }
+}
+
+impl FromStr for ItemPattern {
+    type Err = String;
+
+    /// `<category>/<name>`形式（カテゴリは単数形・複数形のどちらも可）
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (category, name) = s.split_once('/')
+            .filter(|(_, name)| !name.is_empty())
+            .ok_or_else(|| format!("Expected <category>/<name>: {}", s))?;
+
+        Ok(Self {
+            item_type: category.parse()?,
+            name: name.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for ItemPattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.item_type.path(), self.name)
+    }
+}
+
+/// パターンに一致するアイテムと、それらが参照するアイテムを再帰的に集める
+///
+/// プレイリスト → レベル、レベル → エンジン・スキン等、エンジン → スキン等、リプレイ → レベルの参照をたどる
+pub fn resolve_selection(db: &PackDatabase, patterns: &[ItemPattern]) -> Result<BTreeSet<(ItemType, String)>> {
+    let mut pending = Vec::new();
+
+    for pattern in patterns {
+        let matched: Vec<(ItemType, String)> = db.items(pattern.item_type).iter()
+            .filter(|item| pattern.matches(pattern.item_type, item.name()))
+            .map(|item| (pattern.item_type, item.name().to_string()))
+            .collect();
+
+        if matched.is_empty() {
+            return Err(ScpError::InvalidFormat(format!("No items match {}", pattern)));
+        }
+        pending.extend(matched);
+    }
+
+    let mut selected = BTreeSet::new();
+
+    while let Some((item_type, name)) = pending.pop() {
+        if selected.contains(&(item_type, name.clone())) {
+            continue;
+        }
+
+        let items = db.items(item_type);
+        let Some(item) = items.iter().find(|item| item.name() == name) else {
+            println!("Warning: referenced item not found: {}/{}", item_type.path(), name);
+            continue;
+        };
+
+        collect_references(db, *item, &mut pending);
+        selected.insert((item_type, name));
+    }
+
+    Ok(selected)
+}
+
+/// アイテムが参照しているアイテムを集める
+///
+/// 埋め込みアイテムは、同名のアイテムがdb.jsonにあればそれも選択し（`unpack`は参照を常に埋め込みで出力する）、
+/// 埋め込みアイテム自身の参照先もたどる
+fn collect_references(db: &PackDatabase, item: &dyn Item, pending: &mut Vec<(ItemType, String)>) {
+    for link in item.links() {
+        match link.embedded {
+            Some(embedded) => {
+                if db.items(link.item_type).iter().any(|item| item.name() == link.name) {
+                    pending.push((link.item_type, link.name.to_string()));
+                }
+                collect_references(db, embedded, pending);
+            }
+            None => pending.push((link.item_type, link.name.to_string())),
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use walkdir::WalkDir;
+use zip::ZipArchive;
+
+use crate::error::Result;
+use crate::model::PackDatabase;
+use crate::pack_extractor::PackExtractor;
+use crate::static_tree::EntryData;
+use crate::utils::{
+    encode_entry_path, join_entry_path, normalize_path, prepare_output_dir, repository_hash, validate_pack_dir,
+    validate_scp_file, EXTRA_DIR,
+};
+
+/// packの内容（db.json・repository・extraディレクトリ）
+#[derive(Debug, Clone)]
+pub struct PackSource {
+    /// レポートに表示する入力名
+    pub label: String,
+    pub db: PackDatabase,
+    /// repositoryのファイル名と内容
+    pub repository: Vec<(String, EntryData)>,
+    /// extraディレクトリ内の相対パスと内容
+    pub extra: Vec<(String, EntryData)>,
+}
+
+impl PackSource {
+    /// packディレクトリまたはSCPファイルを読み込む
+    pub fn load(extractor: &PackExtractor, input: &Path) -> Result<Self> {
+        if input.is_dir() {
+            Self::from_pack_dir(input)
+        } else {
+            Self::from_scp(extractor, input)
+        }
+    }
+
+    /// packディレクトリを読み込む（ファイルの内容は書き込み時に読む）
+    pub fn from_pack_dir(pack_dir: &Path) -> Result<Self> {
+        validate_pack_dir(pack_dir)?;
+
+        Ok(Self {
+            label: pack_dir.display().to_string(),
+            db: PackDatabase::load(pack_dir)?,
+            repository: dir_files(&pack_dir.join("repository"))?,
+            extra: dir_files(&pack_dir.join(EXTRA_DIR))?,
+        })
+    }
+
+    /// SCPファイルを`unpack`と同じようにメモリ上に展開
+    pub fn from_scp(extractor: &PackExtractor, scp_file: &Path) -> Result<Self> {
+        validate_scp_file(scp_file)?;
+
+        let mut archive = ZipArchive::new(File::open(scp_file)?)?;
+        let (db, extra_entries) = extractor.read_pack(&mut archive)?;
+
+        let mut repository = Vec::new();
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            let hash = repository_hash(file.name())
+                .filter(|_| !file.is_dir())
+                .map(str::to_string);
+
+            if let Some(hash) = hash {
+                let mut buffer = Vec::new();
+                file.read_to_end(&mut buffer)?;
+                repository.push((hash, EntryData::Bytes(buffer)));
+            }
+        }
+
+        Ok(Self {
+            label: scp_file.display().to_string(),
+            db,
+            repository,
+            extra: extra_entries.into_iter()
+                .map(|(path, content)| (encode_entry_path(&path), EntryData::Bytes(content)))
+                .collect(),
+        })
+    }
+
+    /// packディレクトリとして書き込み
+    pub fn write(&self, output_dir: &Path) -> Result<()> {
+        prepare_output_dir(output_dir)?;
+
+        let repo_dir = output_dir.join("repository");
+        std::fs::create_dir_all(&repo_dir)?;
+        for (name, data) in &self.repository {
+            std::fs::write(join_entry_path(&repo_dir, name)?, data.read()?)?;
+        }
+
+        for (path, data) in &self.extra {
+            let output_path = join_entry_path(&output_dir.join(EXTRA_DIR), path)?;
+            if let Some(parent) = output_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(output_path, data.read()?)?;
+        }
+
+        std::fs::write(output_dir.join("db.json"), serde_json::to_string_pretty(&self.db)?)?;
+        Ok(())
+    }
+}
+
+/// ディレクトリ内のファイルを相対パスで列挙
+fn dir_files(dir: &Path) -> Result<Vec<(String, EntryData)>> {
+    let mut files = Vec::new();
+    if !dir.exists() {
+        return Ok(files);
+    }
+
+    for entry in WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry.map_err(|e| std::io::Error::other(
+            format!("Failed to read {}: {}", dir.display(), e)
+        ))?;
+
+        if entry.path().is_file() {
+            files.push((normalize_path(entry.path(), dir)?, EntryData::File(entry.path().to_path_buf())));
+        }
+    }
+
+    Ok(files)
+}
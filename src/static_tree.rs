@@ -0,0 +1,109 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::Result;
+
+/// SCPのエントリの内容
+#[derive(Debug, Clone)]
+pub enum EntryData {
+    /// メモリ上のデータ
+    Bytes(Vec<u8>),
+    /// 書き込み時に読み込むファイル
+    File(PathBuf),
+}
+
+impl EntryData {
+    pub fn read(&self) -> Result<Cow<'_, [u8]>> {
+        match self {
+            EntryData::Bytes(bytes) => Ok(Cow::Borrowed(bytes)),
+            EntryData::File(path) => Ok(Cow::Owned(std::fs::read(path)?)),
+        }
+    }
+
+    /// 内容のバイト数（ファイルは読み込まずに調べる）
+    pub fn size(&self) -> Result<u64> {
+        match self {
+            EntryData::Bytes(bytes) => Ok(bytes.len() as u64),
+            EntryData::File(path) => Ok(path.metadata()?.len()),
+        }
+    }
+}
+
+/// アーカイブなどから読み込んだエントリ（パスと内容）
+pub type RawEntries = Vec<(String, Vec<u8>)>;
+
+/// SCPに書き込むエントリの一覧（追加順を保持）
+#[derive(Debug, Clone, Default)]
+pub struct StaticTree {
+    entries: Vec<(String, EntryData)>,
+    index: HashMap<String, usize>,
+}
+
+impl StaticTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// エントリを追加（同じパスが既にある場合はその位置で置き換える）
+    pub fn insert(&mut self, path: impl Into<String>, data: EntryData) {
+        let path = path.into();
+        match self.index.get(&path) {
+            Some(&position) => self.entries[position].1 = data,
+            None => {
+                self.index.insert(path.clone(), self.entries.len());
+                self.entries.push((path, data));
+            }
+        }
+    }
+
+    /// JSONとしてシリアライズしたエントリを追加
+    pub fn insert_json<T: Serialize + ?Sized>(&mut self, path: impl Into<String>, value: &T) -> Result<()> {
+        let content = serde_json::to_vec(value)?;
+        self.insert(path, EntryData::Bytes(content));
+        Ok(())
+    }
+
+    pub fn remove(&mut self, path: &str) -> Option<EntryData> {
+        let position = self.index.remove(path)?;
+        let (_, data) = self.entries.remove(position);
+        for (index, (path, _)) in self.entries.iter().enumerate().skip(position) {
+            self.index.insert(path.clone(), index);
+        }
+        Some(data)
+    }
+
+    pub fn get(&self, path: &str) -> Option<&EntryData> {
+        self.index.get(path).map(|&position| &self.entries[position].1)
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.index.contains_key(path)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &EntryData)> {
+        self.entries.iter().map(|(path, data)| (path.as_str(), data))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// 2つのエントリの内容が同じか（JSONならキー順などを無視して比較）
+pub fn entries_equivalent(a: &[u8], b: &[u8]) -> bool {
+    if a == b {
+        return true;
+    }
+
+    match (serde_json::from_slice::<Value>(a), serde_json::from_slice::<Value>(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
@@ -1,5 +1,20 @@
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
 use crate::error::{Result, ScpError};
+use crate::model::ItemType;
+use zip::DateTime;
+
+/// 生成できないエントリをそのまま保存するpackディレクトリ内のディレクトリ
+pub const EXTRA_DIR: &str = "extra";
+
+/// SCP内のrepositoryのエントリのパスの接頭辞
+pub const REPOSITORY_PREFIX: &str = "static/sonolus/repository/";
+
+/// repositoryのエントリならファイル名（ハッシュ）を返す
+pub fn repository_hash(path: &str) -> Option<&str> {
+    path.strip_prefix(REPOSITORY_PREFIX)
+        .and_then(|rest| rest.split('/').next_back())
+        .filter(|hash| !hash.is_empty())
+}
 
 /// ファイルパスを正規化し、スラッシュ区切りの相対パスに変換
 pub fn normalize_path(path: &Path, base: &Path) -> Result<String> {
@@ -50,4 +65,165 @@ pub fn prepare_output_dir(output_dir: &Path) -> Result<()> {
         std::fs::create_dir_all(output_dir)?;
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// ロケール別の出力ファイル名を作成（例: output.scp → output.ja.scp）
+pub fn locale_output_path(output_path: &Path, locale: &str) -> PathBuf {
+    let stem = output_path.file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    
+    match output_path.extension() {
+        Some(ext) => output_path.with_file_name(format!("{}.{}.{}", stem, locale, ext.to_string_lossy())),
+        None => output_path.with_file_name(format!("{}.{}", stem, locale)),
+    }
+}
+
+/// ロケール別のファイル名からロケールを取り出す（例: output.ja.scp → ja）
+pub fn locale_from_path(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_str()?;
+    let (_, locale) = stem.rsplit_once('.')?;
+    
+    if locale.is_empty() {
+        None
+    } else {
+        Some(locale.to_string())
+    }
+}
+
+/// カテゴリのlistファイルのパス
+///
+/// 1ページ目は`<category>/list`、2ページ目以降や検索結果はクライアントが要求するURLと同じ
+/// `<category>/list?<filter>&page=<n>`（ページは0始まり）に置く
+pub fn list_page_path(item_type: ItemType, filter: Option<&str>, page: usize) -> String {
+    let mut query = Vec::new();
+    if let Some(filter) = filter {
+        query.push(filter.to_string());
+    }
+    if page > 0 {
+        query.push(format!("page={}", page));
+    }
+    
+    if query.is_empty() {
+        format!("static/sonolus/{}/list", item_type.path())
+    } else {
+        format!("static/sonolus/{}/list?{}", item_type.path(), query.join("&"))
+    }
+}
+
+/// エントリのパスをファイル名に使えない文字をエスケープしたパスに変換（例: list?page=1 → list%3Fpage=1）
+pub fn encode_entry_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for c in path.chars() {
+        match c {
+            '%' | '?' | '*' | ':' | '<' | '>' | '|' | '"' | '\\' => {
+                encoded.push_str(&format!("%{:02X}", c as u32));
+            }
+            _ => encoded.push(c),
+        }
+    }
+    encoded
+}
+
+/// `encode_entry_path`でエスケープしたパスを元に戻す
+pub fn decode_entry_path(path: &str) -> String {
+    let mut decoded = String::with_capacity(path.len());
+    let mut rest = path;
+    
+    while let Some(position) = rest.find('%') {
+        decoded.push_str(&rest[..position]);
+        let code = rest.get(position + 1..position + 3)
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match code {
+            Some(code) => {
+                decoded.push(code as char);
+                rest = &rest[position + 3..];
+            }
+            None => {
+                decoded.push('%');
+                rest = &rest[position + 1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// SCP内のエントリのパスを`base`の下のファイルパスに変換
+///
+/// 絶対パスや`..`を含むパスは`base`の外に書き込めてしまうため（zip-slip）エラーにする
+pub fn join_entry_path(base: &Path, path: &str) -> Result<PathBuf> {
+    let relative = Path::new(path);
+    let is_safe = relative.components().next().is_some()
+        && relative.components().all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if !is_safe {
+        return Err(ScpError::InvalidPath(format!("Entry path escapes the output directory: {}", path)));
+    }
+    
+    Ok(base.join(relative))
+}
+
+/// `*`（任意の文字列）と`?`（任意の1文字）を使ったパターンに一致するか
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    
+    let (mut p, mut t) = (0, 0);
+    // 直前の`*`の位置と、そこから照合を再開するtextの位置
+    let mut backtrack: Option<(usize, usize)> = None;
+    
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// UNIX時刻（秒）をZIPの更新日時に変換（ZIPで表せない1980年より前は1980-01-01 00:00:00）
+pub fn zip_datetime_from_unix(seconds: i64) -> Result<DateTime> {
+    let days = seconds.div_euclid(86400);
+    let time = seconds.rem_euclid(86400);
+
+    // 1970-01-01からの日数を年月日に変換
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    if year < 1980 {
+        return Ok(DateTime::default());
+    }
+
+    u16::try_from(year).ok()
+        .and_then(|year| DateTime::from_date_and_time(
+            year,
+            month as u8,
+            day as u8,
+            (time / 3600) as u8,
+            (time % 3600 / 60) as u8,
+            (time % 60) as u8,
+        ).ok())
+        .ok_or_else(|| ScpError::InvalidFormat(format!("Timestamp is out of the ZIP range: {}", seconds)))
+}
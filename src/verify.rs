@@ -0,0 +1,230 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::io::{Cursor, Read, Seek};
+use std::path::Path;
+use serde::Serialize;
+use serde_json::Value;
+use walkdir::WalkDir;
+use zip::ZipArchive;
+
+use crate::error::Result;
+use crate::json_diff::{diff_json, FieldChange};
+use crate::pack_archiver::PackArchiver;
+use crate::pack_extractor::PackExtractor;
+use crate::static_tree::{entries_equivalent, EntryData, RawEntries};
+use crate::utils::{
+    encode_entry_path, normalize_path, repository_hash, validate_pack_dir, validate_scp_file, EXTRA_DIR, REPOSITORY_PREFIX,
+};
+
+/// エントリ単位の変更
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum EntryChange {
+    /// 変換後にだけあるエントリ
+    Added,
+    /// 変換後に無くなったエントリ
+    Removed,
+    /// JSONのフィールドが変わったエントリ
+    Modified { fields: Vec<FieldChange> },
+    /// 内容が変わったJSON以外のエントリ
+    BinaryModified { old_size: usize, new_size: usize },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryDiff {
+    pub path: String,
+    #[serde(flatten)]
+    pub change: EntryChange,
+}
+
+/// 往復変換の検証結果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoundtripReport {
+    pub input: String,
+    /// 比較したエントリ数（元の入力のエントリ数）
+    pub entries_compared: usize,
+    pub differences: Vec<EntryDiff>,
+}
+
+impl RoundtripReport {
+    /// 元の入力と往復変換の結果が一致したか
+    pub fn is_lossless(&self) -> bool {
+        self.differences.is_empty()
+    }
+}
+
+impl fmt::Display for RoundtripReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Round-trip verification: {}", self.input)?;
+        writeln!(f, "Compared {} entries", self.entries_compared)?;
+
+        for diff in &self.differences {
+            match &diff.change {
+                EntryChange::Added => writeln!(f, "  + {}", diff.path)?,
+                EntryChange::Removed => writeln!(f, "  - {}", diff.path)?,
+                EntryChange::Modified { fields } => {
+                    writeln!(f, "  ~ {}", diff.path)?;
+                    for field in fields {
+                        writeln!(f, "      {}", field)?;
+                    }
+                }
+                EntryChange::BinaryModified { old_size, new_size } => {
+                    writeln!(f, "  ~ {} (binary, {} -> {} bytes)", diff.path, old_size, new_size)?;
+                }
+            }
+        }
+
+        if self.is_lossless() {
+            write!(f, "OK: no differences")
+        } else {
+            write!(f, "FAILED: {} entries differ", self.differences.len())
+        }
+    }
+}
+
+/// packディレクトリまたはSCPファイルをメモリ上で往復変換し、元の内容と比較
+pub fn verify_roundtrip(archiver: &PackArchiver, extractor: &PackExtractor, input: &Path) -> Result<RoundtripReport> {
+    let (original, regenerated) = if input.is_dir() {
+        roundtrip_pack(archiver, extractor, input)?
+    } else {
+        roundtrip_scp(archiver, extractor, input)?
+    };
+
+    Ok(RoundtripReport {
+        input: input.display().to_string(),
+        entries_compared: original.len(),
+        differences: compare_entries(&original, &regenerated),
+    })
+}
+
+/// pack → SCP → pack（エントリはpackディレクトリ内の相対パス）
+fn roundtrip_pack(
+    archiver: &PackArchiver,
+    extractor: &PackExtractor,
+    pack_dir: &Path,
+) -> Result<(RawEntries, RawEntries)> {
+    validate_pack_dir(pack_dir)?;
+
+    let mut original = vec![("db.json".to_string(), std::fs::read(pack_dir.join("db.json"))?)];
+    for dir in ["repository", EXTRA_DIR] {
+        read_dir_entries(pack_dir, &pack_dir.join(dir), &mut original)?;
+    }
+
+    let tree = archiver.build(pack_dir)?;
+    let (scp, _) = archiver.write_archive(&tree, Cursor::new(Vec::new()))?;
+    let mut archive = ZipArchive::new(scp)?;
+    let (db, extra_entries) = extractor.read_pack(&mut archive)?;
+
+    let mut regenerated = vec![("db.json".to_string(), serde_json::to_vec_pretty(&db)?)];
+    for (path, content) in read_archive_entries(&mut archive)? {
+        if let Some(hash) = repository_hash(&path) {
+            regenerated.push((format!("repository/{}", hash), content));
+        }
+    }
+    for (path, content) in extra_entries {
+        regenerated.push((format!("{}/{}", EXTRA_DIR, encode_entry_path(&path)), content));
+    }
+
+    Ok((original, regenerated))
+}
+
+/// SCP → pack → SCP（エントリはSCP内のパス）
+fn roundtrip_scp(
+    archiver: &PackArchiver,
+    extractor: &PackExtractor,
+    scp_file: &Path,
+) -> Result<(RawEntries, RawEntries)> {
+    validate_scp_file(scp_file)?;
+
+    let mut archive = ZipArchive::new(File::open(scp_file)?)?;
+    let original = read_archive_entries(&mut archive)?;
+    let (db, extra_entries) = extractor.read_pack(&mut archive)?;
+
+    // packディレクトリへの展開と同じくrepositoryはファイル名だけを引き継ぐ
+    let mut tree = archiver.build_static_tree(&db)?;
+    for (path, content) in &original {
+        if let Some(hash) = repository_hash(path) {
+            tree.insert(format!("{}{}", REPOSITORY_PREFIX, hash), EntryData::Bytes(content.clone()));
+        }
+    }
+    for (path, content) in extra_entries {
+        tree.insert(path, EntryData::Bytes(content));
+    }
+
+    let regenerated = tree.iter()
+        .map(|(path, data)| Ok((path.to_string(), data.read()?.into_owned())))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok((original, regenerated))
+}
+
+/// 元のエントリの順に比較し、変換後にだけあるエントリを最後に並べる
+fn compare_entries(original: &[(String, Vec<u8>)], regenerated: &[(String, Vec<u8>)]) -> Vec<EntryDiff> {
+    let regenerated_index: HashMap<&str, &[u8]> = regenerated.iter()
+        .map(|(path, content)| (path.as_str(), content.as_slice()))
+        .collect();
+    let original_paths: HashSet<&str> = original.iter().map(|(path, _)| path.as_str()).collect();
+    let mut differences = Vec::new();
+
+    for (path, content) in original {
+        let change = match regenerated_index.get(path.as_str()) {
+            None => Some(EntryChange::Removed),
+            Some(other) if entries_equivalent(content, other) => None,
+            Some(other) => Some(
+                match (serde_json::from_slice::<Value>(content), serde_json::from_slice::<Value>(other)) {
+                    (Ok(old), Ok(new)) => EntryChange::Modified { fields: diff_json(&old, &new) },
+                    _ => EntryChange::BinaryModified { old_size: content.len(), new_size: other.len() },
+                }
+            ),
+        };
+        if let Some(change) = change {
+            differences.push(EntryDiff { path: path.clone(), change });
+        }
+    }
+
+    for (path, _) in regenerated {
+        if !original_paths.contains(path.as_str()) {
+            differences.push(EntryDiff { path: path.clone(), change: EntryChange::Added });
+        }
+    }
+
+    differences
+}
+
+/// アーカイブ内のディレクトリ以外の全エントリを読み込み
+fn read_archive_entries<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<RawEntries> {
+    let mut entries = Vec::new();
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        if !file.is_dir() {
+            let mut buffer = Vec::new();
+            file.read_to_end(&mut buffer)?;
+            entries.push((file.name().to_string(), buffer));
+        }
+    }
+
+    Ok(entries)
+}
+
+/// packディレクトリ内のファイルを相対パスで読み込み
+fn read_dir_entries(pack_dir: &Path, dir: &Path, entries: &mut RawEntries) -> Result<()> {
+    if !dir.exists() {
+        return Ok(());
+    }
+
+    for entry in WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry.map_err(|e| std::io::Error::other(
+            format!("Failed to read {}: {}", dir.display(), e)
+        ))?;
+
+        if entry.path().is_file() {
+            let path = normalize_path(entry.path(), pack_dir)?;
+            entries.push((path, std::fs::read(entry.path())?));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,144 @@
+//! テスト用のpackを組み立てる
+
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+use sha1::{Digest, Sha1};
+
+/// テストごとの空の一時ディレクトリ
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("scp-pack-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// `content`のSHA-1を名前にしたrepositoryのファイルを参照するSRL
+pub fn srl(content: &str) -> Value {
+    let hash = format!("{:x}", Sha1::digest(content.as_bytes()));
+    json!({ "hash": hash, "url": format!("/sonolus/repository/{}", hash) })
+}
+
+fn text(value: &str) -> Value {
+    json!({ "en": value })
+}
+
+fn base(name: &str, version: u32) -> Value {
+    json!({
+        "name": name,
+        "version": version,
+        "title": text(name),
+        "subtitle": text("subtitle"),
+        "author": text("author"),
+        "tags": [],
+        "thumbnail": srl(&format!("{}-thumbnail", name)),
+    })
+}
+
+fn with(mut item: Value, fields: Value) -> Value {
+    let object = item.as_object_mut().unwrap();
+    for (key, value) in fields.as_object().unwrap() {
+        object.insert(key.clone(), value.clone());
+    }
+    item
+}
+
+pub fn skin(name: &str) -> Value {
+    with(base(name, 4), json!({ "data": srl(&format!("{}-data", name)), "texture": srl(&format!("{}-texture", name)) }))
+}
+
+pub fn background(name: &str) -> Value {
+    with(base(name, 2), json!({
+        "data": srl(&format!("{}-data", name)),
+        "image": srl(&format!("{}-image", name)),
+        "configuration": srl(&format!("{}-configuration", name)),
+    }))
+}
+
+pub fn effect(name: &str) -> Value {
+    with(base(name, 5), json!({ "data": srl(&format!("{}-data", name)), "audio": srl(&format!("{}-audio", name)) }))
+}
+
+pub fn particle(name: &str) -> Value {
+    with(base(name, 3), json!({ "data": srl(&format!("{}-data", name)), "texture": srl(&format!("{}-texture", name)) }))
+}
+
+/// スキン等を`embedded`なら埋め込み、そうでなければ名前で参照するエンジン
+pub fn engine(name: &str, parts: &str, embedded: bool) -> Value {
+    let part = |item: Value| if embedded { item } else { json!(parts) };
+    with(base(name, 13), json!({
+        "skin": part(skin(parts)),
+        "background": part(background(parts)),
+        "effect": part(effect(parts)),
+        "particle": part(particle(parts)),
+        "playData": srl(&format!("{}-playData", name)),
+        "watchData": srl(&format!("{}-watchData", name)),
+        "previewData": srl(&format!("{}-previewData", name)),
+        "tutorialData": srl(&format!("{}-tutorialData", name)),
+        "configuration": srl(&format!("{}-configuration", name)),
+    }))
+}
+
+/// `engine`は埋め込みのエンジンまたはエンジンの名前
+pub fn level(name: &str, engine: Value) -> Value {
+    let mut level = with(base(name, 1), json!({
+        "rating": 10,
+        "artists": text("artists"),
+        "engine": engine,
+        "useSkin": { "useDefault": true },
+        "useBackground": { "useDefault": true },
+        "useEffect": { "useDefault": true },
+        "useParticle": { "useDefault": true },
+        "cover": srl(&format!("{}-cover", name)),
+        "bgm": srl(&format!("{}-bgm", name)),
+        "data": srl(&format!("{}-data", name)),
+    }));
+    level.as_object_mut().unwrap().remove("subtitle");
+    level.as_object_mut().unwrap().remove("thumbnail");
+    level
+}
+
+/// エンジン`eng`（スキン等`parts`を名前で参照）と、
+/// それを埋め込んだレベル`lv-embed`・名前で参照するレベル`lv-name`を持つdb.json
+pub fn engine_db() -> Value {
+    json!({
+        "skins": [skin("parts")],
+        "backgrounds": [background("parts")],
+        "effects": [effect("parts")],
+        "particles": [particle("parts")],
+        "engines": [engine("eng", "parts", false)],
+        "levels": [
+            level("lv-embed", engine("eng", "parts", true)),
+            level("lv-name", json!("eng")),
+        ],
+    })
+}
+
+/// db.jsonと、SRLが参照するrepositoryのファイル（内容はハッシュと一致しない仮のもの）を書き込む
+pub fn write_pack(dir: &Path, db: &Value) {
+    let repository = dir.join("repository");
+    fs::create_dir_all(&repository).unwrap();
+    fs::write(dir.join("db.json"), serde_json::to_string_pretty(db).unwrap()).unwrap();
+
+    let mut srls = Vec::new();
+    collect_srl_hashes(db, &mut srls);
+    for hash in srls {
+        fs::write(repository.join(&hash), format!("content of {}", hash)).unwrap();
+    }
+}
+
+fn collect_srl_hashes(value: &Value, hashes: &mut Vec<String>) {
+    match value {
+        Value::Object(object) => {
+            if let (Some(Value::String(hash)), Some(Value::String(_))) = (object.get("hash"), object.get("url")) {
+                hashes.push(hash.clone());
+            }
+            object.values().for_each(|value| collect_srl_hashes(value, hashes));
+        }
+        Value::Array(array) => array.iter().for_each(|value| collect_srl_hashes(value, hashes)),
+        _ => {}
+    }
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use scp_pack::pack_archiver::PackArchiver;
+use zip::ZipArchive;
+
+const PACK_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/example/pack");
+
+fn pack(archiver: &PackArchiver, name: &str) -> Vec<u8> {
+    let dir = common::temp_dir(&format!("deterministic-{}", name));
+    let output = dir.join("output.scp");
+    archiver.archive(Path::new(PACK_DIR), &output).unwrap();
+    let bytes = fs::read(&output).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    bytes
+}
+
+#[test]
+fn packing_twice_gives_identical_bytes() {
+    let archiver = PackArchiver::new().with_deterministic(true);
+
+    let first = pack(&archiver, "first");
+    // ZIPの更新日時は2秒単位なので、時刻が使われていれば差が出るだけ待つ
+    thread::sleep(Duration::from_secs(2));
+    let second = pack(&archiver, "second");
+
+    assert!(first == second, "deterministic packs differ");
+}
+
+#[test]
+fn default_mode_uses_the_current_time() {
+    let bytes = pack(&PackArchiver::new(), "current-time");
+    let mut zip = ZipArchive::new(std::io::Cursor::new(bytes)).unwrap();
+    let time = zip.by_index(0).unwrap().last_modified().unwrap();
+
+    assert_ne!(time.year(), 1980, "entries are not stamped with the current time");
+}
+
+#[test]
+fn output_does_not_depend_on_jobs() {
+    let sequential = pack(&PackArchiver::new().with_deterministic(true).with_jobs(Some(1)), "jobs-1");
+    let parallel = pack(&PackArchiver::new().with_deterministic(true).with_jobs(Some(4)), "jobs-4");
+
+    assert!(sequential == parallel, "packs with different --jobs differ");
+}
+
+#[test]
+fn source_date_epoch_sets_entry_timestamps() {
+    // 2021-01-01 12:34:56 UTC
+    let archiver = PackArchiver::new()
+        .with_deterministic(true)
+        .with_source_date_epoch(Some(1_609_504_496));
+
+    let bytes = pack(&archiver, "epoch");
+    let mut zip = ZipArchive::new(std::io::Cursor::new(bytes)).unwrap();
+    let mut names = Vec::new();
+    for i in 0..zip.len() {
+        let entry = zip.by_index(i).unwrap();
+        let time = entry.last_modified().unwrap();
+        assert_eq!(
+            (time.year(), time.month(), time.day(), time.hour(), time.minute(), time.second()),
+            (2021, 1, 1, 12, 34, 56),
+            "{}",
+            entry.name()
+        );
+        assert_eq!(entry.unix_mode(), Some(0o100644), "{}", entry.name());
+        names.push(entry.name().to_string());
+    }
+
+    let mut sorted = names.clone();
+    sorted.sort();
+    assert_eq!(names, sorted);
+}
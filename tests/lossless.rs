@@ -0,0 +1,112 @@
+mod common;
+
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::Path;
+
+use serde_json::Value;
+use scp_pack::pack_archiver::PackArchiver;
+use scp_pack::pack_extractor::PackExtractor;
+use scp_pack::static_tree::entries_equivalent;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use common::temp_dir;
+
+fn write_scp(path: &Path, entries: &[(&str, &[u8])]) {
+    let mut zip = ZipWriter::new(File::create(path).unwrap());
+    for (name, content) in entries {
+        zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+        zip.write_all(content).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+fn read_scp(path: &Path) -> Vec<(String, Vec<u8>)> {
+    let mut archive = ZipArchive::new(File::open(path).unwrap()).unwrap();
+    let mut entries = Vec::new();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i).unwrap();
+        let mut content = Vec::new();
+        file.read_to_end(&mut content).unwrap();
+        entries.push((file.name().to_string(), content));
+    }
+    entries.sort();
+    entries
+}
+
+#[test]
+fn lossless_unpack_and_pack_reproduce_the_scp() {
+    let dir = temp_dir("lossless-roundtrip");
+    let pack_dir = dir.join("pack");
+    common::write_pack(&pack_dir, &common::engine_db());
+
+    // 生成したSCPに、packから作れない個別ページの値と未知のエントリを加える
+    let generated = dir.join("generated.scp");
+    PackArchiver::new().archive(&pack_dir, &generated).unwrap();
+    let mut entries = read_scp(&generated);
+    for (path, content) in &mut entries {
+        if path == "static/sonolus/levels/lv-name" {
+            let mut page: Value = serde_json::from_slice(content).unwrap();
+            page["hasCommunity"] = Value::Bool(true);
+            *content = serde_json::to_vec(&page).unwrap();
+        }
+    }
+    entries.push(("static/sonolus/custom?query=1".to_string(), b"custom entry".to_vec()));
+
+    let original = dir.join("original.scp");
+    let borrowed: Vec<(&str, &[u8])> = entries.iter().map(|(path, content)| (path.as_str(), content.as_slice())).collect();
+    write_scp(&original, &borrowed);
+
+    let unpacked = dir.join("unpacked");
+    let repacked = dir.join("repacked.scp");
+    PackExtractor::new().with_lossless(true).extract(&original, &unpacked).unwrap();
+    PackArchiver::new().archive(&unpacked, &repacked).unwrap();
+
+    let original = read_scp(&original);
+    let repacked = read_scp(&repacked);
+    let names = |entries: &[(String, Vec<u8>)]| entries.iter().map(|(path, _)| path.clone()).collect::<Vec<_>>();
+    assert_eq!(names(&original), names(&repacked));
+    for ((path, a), (_, b)) in original.iter().zip(&repacked) {
+        assert!(entries_equivalent(a, b), "{} differs", path);
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn entries_outside_the_output_directory_are_rejected() {
+    let dir = temp_dir("zip-slip");
+    let escaped = dir.join("PWNED");
+    let absolute = dir.join("PWNED_ABS");
+
+    // extra/static/sonolus/../../../../PWNED はdirの直下を指す
+    let cases = [
+        "static/sonolus/../../../../PWNED".to_string(),
+        absolute.to_string_lossy().to_string(),
+    ];
+    for (index, name) in cases.iter().enumerate() {
+        let scp = dir.join(format!("evil-{}.scp", index));
+        write_scp(&scp, &[(name, b"pwned")]);
+
+        let result = PackExtractor::new()
+            .with_lossless(true)
+            .extract(&scp, &dir.join(format!("out-{}", index)));
+        assert!(result.is_err(), "{} was extracted", name);
+    }
+
+    assert!(!escaped.exists());
+    assert!(!absolute.exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn repository_entries_outside_the_output_directory_are_rejected() {
+    let dir = temp_dir("zip-slip-repository");
+    let scp = dir.join("evil.scp");
+    write_scp(&scp, &[("static/sonolus/repository/..", b"pwned")]);
+
+    let result = PackExtractor::new().extract(&scp, &dir.join("out"));
+    assert!(result.is_err());
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use serde_json::{json, Value};
+use scp_pack::merge::{ConflictPolicy, MergeReport, PackMerger, Resolution};
+use scp_pack::model::PackDatabase;
+use scp_pack::source::PackSource;
+
+fn source(label: &str, db: Value) -> PackSource {
+    PackSource {
+        label: label.to_string(),
+        db: PackDatabase::from_value(db).unwrap(),
+        repository: Vec::new(),
+        extra: Vec::new(),
+    }
+}
+
+/// `a`と`b`はどちらもスキン`parts`を持ち、`b`の方が`version`が大きく内容も異なる
+fn merge(policy: ConflictPolicy) -> (Value, MergeReport) {
+    let mut newer = common::skin("parts");
+    newer["version"] = json!(5);
+    newer["title"] = json!({ "en": "newer" });
+
+    let a = source("a", json!({
+        "skins": [common::skin("parts")],
+        "engines": [common::engine("eng", "parts", false)],
+    }));
+    let b = source("b", json!({
+        "skins": [newer],
+        "engines": [common::engine("other", "parts", false)],
+    }));
+
+    let (merged, report) = PackMerger::new().with_policy(policy).merge(vec![a, b], "merged").unwrap();
+    (serde_json::to_value(&merged.db).unwrap(), report)
+}
+
+fn names(db: &Value, category: &str) -> Vec<String> {
+    db[category].as_array().unwrap().iter().map(|item| item["name"].as_str().unwrap().to_string()).collect()
+}
+
+fn skin_title(db: &Value, name: &str) -> Value {
+    db["skins"].as_array().unwrap().iter().find(|skin| skin["name"] == name).unwrap()["title"]["en"].clone()
+}
+
+fn resolution(report: &MergeReport) -> &Resolution {
+    assert_eq!(report.conflicts.len(), 1, "{}", report);
+    assert_eq!(report.conflicts[0].subject, "skins/parts");
+    &report.conflicts[0].resolution
+}
+
+#[test]
+fn error_policy_leaves_the_conflict_unresolved() {
+    let (_, report) = merge(ConflictPolicy::Error);
+
+    assert_eq!(resolution(&report), &Resolution::Unresolved);
+    assert!(!report.is_ok());
+}
+
+#[test]
+fn first_policy_keeps_the_earlier_item() {
+    let (db, report) = merge(ConflictPolicy::First);
+
+    assert_eq!(resolution(&report), &Resolution::KeptExisting);
+    assert_eq!(names(&db, "skins"), ["parts"]);
+    assert_eq!(skin_title(&db, "parts"), "parts");
+}
+
+#[test]
+fn last_policy_keeps_the_later_item() {
+    let (db, report) = merge(ConflictPolicy::Last);
+
+    assert_eq!(resolution(&report), &Resolution::Replaced);
+    assert_eq!(names(&db, "skins"), ["parts"]);
+    assert_eq!(skin_title(&db, "parts"), "newer");
+}
+
+#[test]
+fn highest_version_policy_keeps_the_newer_item() {
+    let (db, report) = merge(ConflictPolicy::HighestVersion);
+
+    assert_eq!(resolution(&report), &Resolution::Replaced);
+    assert_eq!(skin_title(&db, "parts"), "newer");
+    assert_eq!(report.conflicts[0].existing_version, Some(4));
+    assert_eq!(report.conflicts[0].incoming_version, Some(5));
+}
+
+#[test]
+fn rename_policy_rewrites_references_to_the_renamed_item() {
+    let (db, report) = merge(ConflictPolicy::Rename);
+
+    assert_eq!(resolution(&report), &Resolution::Renamed { name: "b-parts".to_string() });
+    assert!(report.is_ok());
+    assert_eq!(names(&db, "skins"), ["parts", "b-parts"]);
+    assert_eq!(skin_title(&db, "b-parts"), "newer");
+
+    let engine = |name: &str| db["engines"].as_array().unwrap().iter().find(|engine| engine["name"] == name).unwrap().clone();
+    assert_eq!(engine("eng")["skin"], "parts");
+    assert_eq!(engine("other")["skin"], "b-parts");
+}
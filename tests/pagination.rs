@@ -0,0 +1,67 @@
+mod common;
+
+use std::fs::File;
+
+use serde_json::{json, Value};
+use scp_pack::model::ItemType;
+use scp_pack::pack_archiver::PackArchiver;
+use scp_pack::pack_extractor::PackExtractor;
+use zip::ZipArchive;
+
+const NAMES: [&str; 5] = ["a", "b", "c", "d", "e"];
+
+fn skins_db() -> Value {
+    json!({ "skins": NAMES.iter().map(|name| common::skin(name)).collect::<Vec<_>>() })
+}
+
+fn list_names(list: &Value) -> Vec<&str> {
+    list["items"].as_array().unwrap().iter().map(|item| item["name"].as_str().unwrap()).collect()
+}
+
+#[test]
+fn lists_are_split_into_pages() {
+    let dir = common::temp_dir("pagination-split");
+    common::write_pack(&dir, &skins_db());
+
+    let tree = PackArchiver::new().with_page_size(Some(2)).build(&dir).unwrap();
+    let page = |path: &str| -> Value {
+        let data = tree.get(path).unwrap_or_else(|| panic!("{} is missing", path)).read().unwrap();
+        serde_json::from_slice(&data).unwrap()
+    };
+
+    let pages = [
+        page("static/sonolus/skins/list"),
+        page("static/sonolus/skins/list?page=1"),
+        page("static/sonolus/skins/list?page=2"),
+    ];
+    for list in &pages {
+        assert_eq!(list["pageCount"], 3);
+    }
+    assert_eq!(list_names(&pages[0]), ["a", "b"]);
+    assert_eq!(list_names(&pages[1]), ["c", "d"]);
+    assert_eq!(list_names(&pages[2]), ["e"]);
+    assert!(!tree.contains("static/sonolus/skins/list?page=3"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn unpack_reads_every_page() {
+    let dir = common::temp_dir("pagination-roundtrip");
+    let pack_dir = dir.join("pack");
+    let scp = dir.join("paged.scp");
+    common::write_pack(&pack_dir, &skins_db());
+
+    PackArchiver::new().with_page_size(Some(2)).archive(&pack_dir, &scp).unwrap();
+    let mut archive = ZipArchive::new(File::open(&scp).unwrap()).unwrap();
+
+    let (db, _) = PackExtractor::new().read_pack(&mut archive).unwrap();
+    let names: Vec<&str> = db.items(ItemType::Skin).iter().map(|item| item.name()).collect();
+    assert_eq!(names, NAMES);
+
+    // --losslessではページサイズも引き継ぐ
+    let (db, _) = PackExtractor::new().with_lossless(true).read_pack(&mut archive).unwrap();
+    assert_eq!(db.config.page_size, Some(2));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+use scp_pack::pack_archiver::PackArchiver;
+use scp_pack::patch::{apply_patch, make_patch};
+
+/// 元のSCPと、スキンの内容を変えてレベルを1つ消した新しいSCPを作る
+fn write_scps(dir: &Path) -> (PathBuf, PathBuf) {
+    let archiver = PackArchiver::new().with_deterministic(true);
+
+    let base_db = common::engine_db();
+    let mut target_db = base_db.clone();
+    target_db["skins"][0]["title"] = json!({ "en": "changed" });
+    target_db["levels"].as_array_mut().unwrap().pop();
+
+    let mut scps = Vec::new();
+    for (name, db) in [("base", base_db), ("target", target_db)] {
+        let pack_dir = dir.join(name);
+        let scp = dir.join(format!("{}.scp", name));
+        common::write_pack(&pack_dir, &db);
+        archiver.archive(&pack_dir, &scp).unwrap();
+        scps.push(scp);
+    }
+    let target = scps.pop().unwrap();
+    (scps.pop().unwrap(), target)
+}
+
+#[test]
+fn applying_a_patch_reproduces_the_target_bytes() {
+    let dir = common::temp_dir("patch-apply");
+    let (base, target) = write_scps(&dir);
+    let patch = dir.join("update.scppatch");
+    let output = dir.join("patched.scp");
+
+    let report = make_patch(&base, &target, &patch).unwrap();
+    assert!(report.unchanged > 0);
+    assert!(!report.modified.is_empty());
+    assert!(!report.deleted.is_empty());
+
+    apply_patch(&base, &patch, &output).unwrap();
+    assert!(fs::read(&output).unwrap() == fs::read(&target).unwrap(), "patched SCP differs from the target");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn patch_is_rejected_for_a_different_base() {
+    let dir = common::temp_dir("patch-wrong-base");
+    let (base, target) = write_scps(&dir);
+    let patch = dir.join("update.scppatch");
+    let output = dir.join("patched.scp");
+
+    make_patch(&base, &target, &patch).unwrap();
+
+    let result = apply_patch(&target, &patch, &output);
+    assert!(result.is_err());
+    assert!(!output.exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
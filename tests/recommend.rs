@@ -0,0 +1,83 @@
+mod common;
+
+use serde_json::{json, Value};
+use scp_pack::config::{RecommendConfig, RecommendStrategy};
+use scp_pack::pack_archiver::PackArchiver;
+
+fn item(name: &str, author: &str, tags: &[&str]) -> Value {
+    json!({
+        "name": name,
+        "author": author,
+        "tags": tags.iter().map(|tag| json!({ "title": tag })).collect::<Vec<_>>(),
+    })
+}
+
+/// db.jsonの順に a, b, c, d, e
+fn candidates() -> Vec<Value> {
+    vec![
+        item("a", "x", &["t1"]),
+        item("b", "y", &["t1", "t2"]),
+        item("c", "x", &[]),
+        item("d", "x", &["t2"]),
+        item("e", "y", &["t1", "t2"]),
+    ]
+}
+
+fn select(strategy: RecommendStrategy, item_count: Option<usize>, target: &str, recommendations: &[&str]) -> Vec<String> {
+    let config = RecommendConfig {
+        title: "#RECOMMENDED".to_string(),
+        icon: None,
+        strategy,
+        item_count,
+    };
+    let candidates = candidates();
+    let target = candidates.iter().find(|item| item["name"] == target).unwrap();
+    let recommendations: Vec<String> = recommendations.iter().map(|name| name.to_string()).collect();
+
+    config.select(target, &candidates, &recommendations).iter()
+        .map(|item| item["name"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn same_category_starts_after_the_item_and_wraps_around() {
+    assert_eq!(select(RecommendStrategy::SameCategory, None, "c", &[]), ["d", "e", "a", "b"]);
+    assert_eq!(select(RecommendStrategy::SameCategory, Some(2), "d", &[]), ["e", "a"]);
+}
+
+#[test]
+fn same_author_keeps_only_items_by_the_same_author() {
+    assert_eq!(select(RecommendStrategy::SameAuthor, None, "c", &[]), ["d", "a"]);
+    assert_eq!(select(RecommendStrategy::SameAuthor, Some(1), "c", &[]), ["d"]);
+}
+
+#[test]
+fn shared_tags_orders_by_the_number_of_shared_tags() {
+    // bと共通するタグはeが2つ、d・aが1つ（同数ならbの次からの順）
+    assert_eq!(select(RecommendStrategy::SharedTags, None, "b", &[]), ["e", "d", "a"]);
+    assert_eq!(select(RecommendStrategy::SharedTags, Some(2), "b", &[]), ["e", "d"]);
+    assert!(select(RecommendStrategy::SharedTags, None, "c", &[]).is_empty());
+}
+
+#[test]
+fn curated_keeps_the_listed_order_and_skips_missing_items() {
+    assert_eq!(select(RecommendStrategy::Curated, None, "a", &["e", "missing", "c"]), ["e", "c"]);
+    assert_eq!(select(RecommendStrategy::Curated, Some(1), "a", &["e", "c"]), ["e"]);
+}
+
+#[test]
+fn default_section_recommends_up_to_five_other_items() {
+    let dir = common::temp_dir("recommend-default");
+    let names = ["s0", "s1", "s2", "s3", "s4", "s5", "s6"];
+    common::write_pack(&dir, &json!({ "skins": names.iter().map(|name| common::skin(name)).collect::<Vec<_>>() }));
+
+    let tree = PackArchiver::new().build(&dir).unwrap();
+    let page: Value = serde_json::from_slice(&tree.get("static/sonolus/skins/s5").unwrap().read().unwrap()).unwrap();
+    let recommended: Vec<&str> = page["sections"][0]["items"].as_array().unwrap().iter()
+        .map(|item| item["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(page["sections"][0]["title"], "#RECOMMENDED");
+    assert_eq!(recommended, ["s6", "s0", "s1", "s2", "s3"]);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use serde_json::{json, Value};
+use scp_pack::pack_archiver::PackArchiver;
+use scp_pack::static_tree::StaticTree;
+
+fn skin(name: &str, author: &str, tags: &[&str]) -> Value {
+    let mut skin = common::skin(name);
+    skin["author"] = json!({ "en": author });
+    skin["tags"] = tags.iter().map(|tag| json!({ "title": { "en": tag } })).collect();
+    skin
+}
+
+fn build(name: &str, search: Value) -> StaticTree {
+    let skins = json!([skin("a", "x", &["t1"]), skin("b", "y", &["t1", "t2"]), skin("c", "y", &[])]);
+    build_skins(name, skins, search)
+}
+
+fn build_skins(name: &str, skins: Value, search: Value) -> StaticTree {
+    let dir = common::temp_dir(name);
+    common::write_pack(&dir, &json!({ "skins": skins, "config": { "search": search } }));
+
+    let tree = PackArchiver::new().build(&dir).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    tree
+}
+
+fn static_json(tree: &StaticTree, path: &str) -> Value {
+    let data = tree.get(path).unwrap_or_else(|| panic!("{} is missing", path)).read().unwrap();
+    serde_json::from_slice(&data).unwrap()
+}
+
+fn list_names(list: &Value) -> Vec<&str> {
+    list["items"].as_array().unwrap().iter().map(|item| item["name"].as_str().unwrap()).collect()
+}
+
+#[test]
+fn search_is_off_by_default() {
+    let tree = build("search-default", json!({}));
+
+    assert_eq!(static_json(&tree, "static/sonolus/skins/info")["search"]["options"], json!([]));
+    assert!(!tree.iter().any(|(path, _)| path.starts_with("static/sonolus/skins/list?")));
+}
+
+#[test]
+fn options_are_generated_from_the_pack() {
+    let tree = build("search-options", json!({ "keywords": true, "tags": true, "authors": true }));
+    let options = static_json(&tree, "static/sonolus/skins/info")["search"]["options"].clone();
+
+    assert_eq!(options, json!([
+        { "query": "keywords", "name": "#KEYWORDS", "type": "text", "placeholder": "#KEYWORDS", "def": "" },
+        { "query": "tags", "name": "#TAGS", "type": "multi", "def": [false, false], "values": ["t1", "t2"] },
+        { "query": "author", "name": "#AUTHOR", "type": "select", "def": 0, "values": ["#ANY", "x", "y"] },
+    ]));
+}
+
+#[test]
+fn results_are_precomputed_for_each_tag_and_author() {
+    let tree = build("search-results", json!({ "keywords": true, "tags": true, "authors": true }));
+    let list = |query: &str| static_json(&tree, &format!("static/sonolus/skins/list?{}", query));
+
+    assert_eq!(list_names(&list("keywords=&tags=00&author=0")), ["a", "b", "c"]);
+    assert_eq!(list_names(&list("keywords=&tags=10&author=0")), ["a", "b"]);
+    assert_eq!(list_names(&list("keywords=&tags=10&author=2")), ["b"]);
+    assert_eq!(list_names(&list("keywords=&tags=00&author=2")), ["b", "c"]);
+    assert_eq!(list_names(&list("keywords=&tags=01&author=1")), Vec::<&str>::new());
+}
+
+#[test]
+fn too_many_combinations_fall_back_to_single_filters() {
+    // タグ32個 × 作者32人で、組み合わせは33 × 33 = 1089
+    let skins: Vec<Value> = (0..32)
+        .map(|index| skin(&format!("s{}", index), &format!("author{}", index), &[&format!("tag{}", index)]))
+        .collect();
+    let tree = build_skins("search-limit", json!(skins), json!({ "tags": true, "authors": true }));
+
+    let results = tree.iter().filter(|(path, _)| path.starts_with("static/sonolus/skins/list?")).count();
+    assert_eq!(results, 1 + 32 + 32);
+    assert!(tree.contains(&format!("static/sonolus/skins/list?tags={}&author=0", "0".repeat(32))));
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use scp_pack::model::{ItemType, PackDatabase};
+use scp_pack::pack_archiver::PackArchiver;
+use scp_pack::select::{resolve_selection, ItemPattern};
+
+fn select(pattern: &str) -> Vec<(ItemType, String)> {
+    let db = PackDatabase::from_value(common::engine_db()).unwrap();
+    let pattern: ItemPattern = pattern.parse().unwrap();
+    resolve_selection(&db, &[pattern]).unwrap().into_iter().collect()
+}
+
+fn expected(level: &str) -> Vec<(ItemType, String)> {
+    let mut expected = vec![
+        (ItemType::Skin, "parts".to_string()),
+        (ItemType::Background, "parts".to_string()),
+        (ItemType::Effect, "parts".to_string()),
+        (ItemType::Particle, "parts".to_string()),
+        (ItemType::Engine, "eng".to_string()),
+        (ItemType::Level, level.to_string()),
+    ];
+    expected.sort();
+    expected
+}
+
+#[test]
+fn embedded_references_select_the_top_level_items() {
+    assert_eq!(select("levels/lv-embed"), expected("lv-embed"));
+}
+
+#[test]
+fn name_references_select_the_referenced_items() {
+    assert_eq!(select("levels/lv-name"), expected("lv-name"));
+}
+
+#[test]
+fn included_pack_keeps_the_closure_and_its_files() {
+    let dir = common::temp_dir("select-pack");
+    common::write_pack(&dir, &common::engine_db());
+
+    let tree = PackArchiver::new()
+        .with_includes(vec!["levels/lv-embed".parse().unwrap()])
+        .build(&dir)
+        .unwrap();
+    let paths: Vec<&str> = tree.iter().map(|(path, _)| path).collect();
+
+    for path in [
+        "static/sonolus/levels/lv-embed",
+        "static/sonolus/engines/eng",
+        "static/sonolus/skins/parts",
+        "static/sonolus/backgrounds/parts",
+        "static/sonolus/effects/parts",
+        "static/sonolus/particles/parts",
+    ] {
+        assert!(paths.contains(&path), "{} is missing", path);
+    }
+    assert!(!paths.contains(&"static/sonolus/levels/lv-name"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
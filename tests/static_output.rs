@@ -0,0 +1,49 @@
+mod common;
+
+use serde_json::{json, Value};
+use scp_pack::pack_archiver::PackArchiver;
+
+fn static_json(db: &Value, path: &str) -> Value {
+    let dir = common::temp_dir(&format!("static-{}", path.replace('/', "-")));
+    common::write_pack(&dir, db);
+
+    let tree = PackArchiver::new().build(&dir).unwrap();
+    let data = tree.get(path).unwrap_or_else(|| panic!("{} is missing", path)).read().unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    serde_json::from_slice(&data).unwrap()
+}
+
+#[test]
+fn name_references_are_embedded_in_static_files() {
+    let page = static_json(&common::engine_db(), "static/sonolus/levels/lv-name");
+
+    let engine = &page["item"]["engine"];
+    assert_eq!(engine["name"], "eng");
+    assert_eq!(engine["skin"]["name"], "parts");
+    assert_eq!(engine["skin"]["title"], "parts");
+    assert_eq!(engine["particle"]["name"], "parts");
+}
+
+#[test]
+fn embedded_references_are_unchanged() {
+    let name = static_json(&common::engine_db(), "static/sonolus/levels/lv-name");
+    let embedded = static_json(&common::engine_db(), "static/sonolus/levels/lv-embed");
+
+    assert_eq!(name["item"]["engine"], embedded["item"]["engine"]);
+}
+
+#[test]
+fn dangling_name_references_are_an_error() {
+    let mut db = common::engine_db();
+    db["levels"] = json!([common::level("lv-dangling", json!("missing-engine"))]);
+
+    let dir = common::temp_dir("static-dangling");
+    common::write_pack(&dir, &db);
+    let result = PackArchiver::new().build(&dir);
+    std::fs::remove_dir_all(&dir).unwrap();
+    let Err(error) = result else {
+        panic!("dangling reference was packed");
+    };
+
+    assert!(error.to_string().contains("levels/lv-dangling references missing item: engines/missing-engine"), "{}", error);
+}